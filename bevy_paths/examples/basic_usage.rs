@@ -16,7 +16,7 @@ struct Level {
 fn main() {
     // Initialize Bevy App
     App::new()
-        .add_plugins(MinimalPlugins)
+        .add_plugins((MinimalPlugins, PathsPlugin::default()))
         .add_systems(Startup, (load_settings, load_level))
         .run();
 }
@@ -0,0 +1,216 @@
+//! Detection and resolution of cloud-sync conflict copies.
+//!
+//! Sync clients create "conflicted copies" next to a file when two machines changed it
+//! at the same time. Recognized patterns:
+//!
+//! - Dropbox / Nextcloud: `save (Laptop's conflicted copy 2024-05-01).sav`
+//! - Syncthing: `save.sync-conflict-20240501-120000-ABCDEFG.sav`
+//! - OneDrive (Windows only): `save-LAPTOP.sav`, where `LAPTOP` is the name of this machine
+//!
+//! A copy is only reported while the file it belongs to exists. Names like
+//! `profile-steamdeck.ron` are common, and treating them as copies would delete or move
+//! user files.
+//!
+//! Steam Cloud resolves conflicts in its own dialog before the game starts and does not
+//! leave a copy next to the file, so there is nothing to detect for it.
+
+use {
+    crate::{
+        TypedPath,
+        display::{redact, redact_path},
+        io,
+        io::PathIoError,
+    },
+    bevy_app::{App, Startup},
+    bevy_ecs::prelude::*,
+    bevy_log::{error, warn},
+    std::{
+        fs,
+        path::{Path, PathBuf},
+        time::SystemTime,
+    },
+};
+
+/// Size and modification time of one side of a conflict.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictSide {
+    /// The file path.
+    pub path: PathBuf,
+    /// The file size in bytes.
+    pub size: u64,
+    /// The last modification time, if available.
+    pub modified: Option<SystemTime>,
+}
+
+/// A file together with a conflicted copy created by a sync client.
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct SyncConflict {
+    /// The file the conflict belongs to.
+    pub original: ConflictSide,
+    /// The conflicted copy.
+    pub conflicted: ConflictSide,
+}
+
+/// How to resolve a [`SyncConflict`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Keep the original file and delete the conflicted copy.
+    KeepOriginal,
+    /// Replace the original file with the conflicted copy.
+    KeepConflicted,
+    /// Keep both, renaming the copy to `name (conflict N).ext`. Returns the new path.
+    KeepBoth,
+}
+
+/// Finds all conflicted copies below `marker` whose original file exists.
+pub fn detect_conflicts<P: TypedPath>(marker: &P) -> Result<Vec<SyncConflict>, PathIoError> {
+    let mut conflicts = Vec::new();
+    for path in io::walk_files(&marker.resolve()?)? {
+        let Some(original) = path
+            .file_name()
+            .and_then(|name| original_name(&name.to_string_lossy()))
+        else {
+            continue;
+        };
+        let original = path.with_file_name(original);
+        if !original.is_file() {
+            continue;
+        }
+        conflicts.push(SyncConflict {
+            original: side(original),
+            conflicted: side(path),
+        });
+    }
+    Ok(conflicts)
+}
+
+/// Resolves `conflict` and returns the path of the surviving copy.
+pub fn resolve_conflict(
+    conflict: &SyncConflict,
+    resolution: ConflictResolution,
+) -> Result<PathBuf, PathIoError> {
+    let original = &conflict.original.path;
+    let conflicted = &conflict.conflicted.path;
+    match resolution {
+        ConflictResolution::KeepOriginal => {
+            fs::remove_file(conflicted).map_err(|e| PathIoError::Io(conflicted.clone(), e))?;
+            Ok(original.clone())
+        }
+        ConflictResolution::KeepConflicted => {
+            fs::rename(conflicted, original).map_err(|e| PathIoError::Io(original.clone(), e))?;
+            Ok(original.clone())
+        }
+        ConflictResolution::KeepBoth => {
+            let target = (1..)
+                .map(|n| numbered(original, &format!("conflict {n}")))
+                .find(|path| !path.exists())
+                .unwrap_or_else(|| original.clone());
+            fs::rename(conflicted, &target).map_err(|e| PathIoError::Io(target.clone(), e))?;
+            Ok(target)
+        }
+    }
+}
+
+/// Extension trait for scanning markers for conflicts at startup.
+pub trait SyncConflictExt {
+    /// Scans `P` at startup and writes a [`SyncConflict`] message for every conflicted copy.
+    fn detect_sync_conflicts<P: TypedPath + Default>(&mut self) -> &mut Self;
+}
+
+impl SyncConflictExt for App {
+    fn detect_sync_conflicts<P: TypedPath + Default>(&mut self) -> &mut Self {
+        self.add_message::<SyncConflict>()
+            .add_systems(Startup, scan_conflicts::<P>)
+    }
+}
+
+fn scan_conflicts<P: TypedPath + Default>(mut messages: MessageWriter<SyncConflict>) {
+    match detect_conflicts(&P::default()) {
+        Ok(conflicts) => {
+            for conflict in conflicts {
+                warn!(
+                    "Sync conflict detected: '{}'",
+                    redact_path(&conflict.conflicted.path)
+                );
+                messages.write(conflict);
+            }
+        }
+        Err(e) => error!(
+            "Failed to scan for sync conflicts: {}",
+            redact(&e.to_string())
+        ),
+    }
+}
+
+/// The name of the file a conflicted copy belongs to, or `None` if `name` is no conflict copy.
+pub(crate) fn original_name(name: &str) -> Option<String> {
+    original_name_on(name, hostname())
+}
+
+/// [`original_name`] for copies created on the machine `host`.
+pub(crate) fn original_name_on(name: &str, host: Option<&str>) -> Option<String> {
+    const SYNCTHING: &str = ".sync-conflict-";
+    if let Some(start) = name.find(SYNCTHING) {
+        let rest = &name[start + SYNCTHING.len()..];
+        let extension = rest.find('.').map_or("", |i| &rest[i..]);
+        return Some(format!("{}{extension}", &name[..start]));
+    }
+    // The conflict suffix is the last group, `save (1) (Laptop's conflicted copy).sav`
+    // belongs to `save (1).sav`.
+    for (start, _) in name.rmatch_indices(" (") {
+        let Some(end) = name[start..].find(')').map(|end| start + end) else {
+            continue;
+        };
+        if name[start..end].contains("conflicted copy") {
+            return Some(format!("{}{}", &name[..start], &name[end + 1..]));
+        }
+    }
+    let host = host?;
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let split = stem.len().checked_sub(host.len() + 1)?;
+    let (original, suffix) = (stem.get(..split)?, stem.get(split..)?);
+    (!original.is_empty() && suffix.starts_with('-') && suffix[1..].eq_ignore_ascii_case(host))
+        .then(|| format!("{original}{extension}"))
+}
+
+/// The name of this machine, which OneDrive appends to its conflict copies.
+#[cfg(windows)]
+fn hostname() -> Option<&'static str> {
+    static HOSTNAME: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+    HOSTNAME
+        .get_or_init(|| {
+            std::env::var("COMPUTERNAME")
+                .ok()
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+        })
+        .as_deref()
+}
+
+/// OneDrive does not mark conflicts with the machine name outside of Windows.
+#[cfg(not(windows))]
+fn hostname() -> Option<&'static str> {
+    None
+}
+
+/// `dir/name (suffix).ext` for `dir/name.ext`.
+pub(crate) fn numbered(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem} ({suffix}).{}", ext.to_string_lossy()),
+        None => format!("{stem} ({suffix})"),
+    };
+    path.with_file_name(name)
+}
+
+fn side(path: PathBuf) -> ConflictSide {
+    let metadata = fs::metadata(&path).ok();
+    ConflictSide {
+        size: metadata.as_ref().map_or(0, |m| m.len()),
+        modified: metadata.and_then(|m| m.modified().ok()),
+        path,
+    }
+}
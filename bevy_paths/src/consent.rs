@@ -0,0 +1,165 @@
+//! A standard location for privacy consent flags.
+//!
+//! Consent decisions (analytics, crash reports, ...) are stored in `.bevy_paths/consent.ron`
+//! below the base path with atomic writes, together with the time of the decision, so they
+//! have one auditable place across games. Games keeping their settings in a config marker
+//! store the file there instead, with
+//! [`PathsPlugin::with_consent_marker`](crate::PathsPlugin::with_consent_marker).
+//! [`PathsPlugin`](crate::PathsPlugin) publishes a [`ConsentChanged`] message for every change.
+//!
+//! ```rust,no_run
+//! use bevy_paths::consent::{self, ConsentState};
+//!
+//! if consent::consent("analytics").unwrap() == ConsentState::Unknown {
+//!     // Ask the player, then:
+//!     consent::set_consent("analytics", ConsentState::Denied).unwrap();
+//! }
+//! ```
+
+use {
+    crate::{PathValidationError, TypedPath, io, io::PathIoError, private::PathResolver},
+    bevy_ecs::prelude::*,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        fs,
+        path::PathBuf,
+        sync::{Mutex, RwLock},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// The default location of the consent file, relative to the base path.
+pub const CONSENT_FILE: &str = ".bevy_paths/consent.ron";
+
+/// The name of the consent file inside the marker set with
+/// [`PathsPlugin::with_consent_marker`](crate::PathsPlugin::with_consent_marker).
+pub const CONSENT_FILE_NAME: &str = "consent.ron";
+
+/// Resolves the directory of the consent marker.
+pub(crate) type ConsentDir = fn() -> Result<PathBuf, PathValidationError>;
+
+/// The player's decision for a consent key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ConsentState {
+    /// The player was not asked yet.
+    #[default]
+    Unknown,
+    /// The player agreed.
+    Granted,
+    /// The player declined.
+    Denied,
+}
+
+/// A stored consent decision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentRecord {
+    /// The decision.
+    pub state: ConsentState,
+    /// When the decision was made, in seconds since the Unix epoch.
+    pub changed_at: u64,
+}
+
+/// Sent when a consent decision changes.
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct ConsentChanged {
+    /// The consent key, e.g. `"analytics"`.
+    pub key: String,
+    /// The new decision.
+    pub state: ConsentState,
+}
+
+type ConsentStore = BTreeMap<String, ConsentRecord>;
+
+static STORE: Mutex<Option<ConsentStore>> = Mutex::new(None);
+static PENDING: Mutex<Vec<ConsentChanged>> = Mutex::new(Vec::new());
+static CONSENT_DIR: RwLock<Option<ConsentDir>> = RwLock::new(None);
+
+/// The [`ConsentDir`] of the marker `P`.
+pub(crate) fn marker_dir<P: TypedPath + Default>() -> Result<PathBuf, PathValidationError> {
+    P::default().resolve()
+}
+
+/// Stores the consent file in the directory returned by `dir`, reloading decisions from there.
+pub(crate) fn set_consent_dir(dir: ConsentDir) {
+    let mut guard = STORE.lock().unwrap_or_else(|e| e.into_inner());
+    *CONSENT_DIR.write().unwrap_or_else(|e| e.into_inner()) = Some(dir);
+    *guard = None;
+}
+
+/// Returns the decision for `key`.
+pub fn consent(key: &str) -> Result<ConsentState, PathIoError> {
+    Ok(consent_record(key)?.map_or(ConsentState::Unknown, |record| record.state))
+}
+
+/// Returns the stored decision for `key` including its timestamp.
+pub fn consent_record(key: &str) -> Result<Option<ConsentRecord>, PathIoError> {
+    let mut guard = STORE.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(loaded(&mut guard)?.get(key).copied())
+}
+
+/// Stores the decision for `key`. Setting [`ConsentState::Unknown`] forgets the decision.
+///
+/// If the file cannot be written, the previous decision stays in effect.
+pub fn set_consent(key: &str, state: ConsentState) -> Result<(), PathIoError> {
+    let mut guard = STORE.lock().unwrap_or_else(|e| e.into_inner());
+    let store = loaded(&mut guard)?;
+    if store
+        .get(key)
+        .map_or(ConsentState::Unknown, |record| record.state)
+        == state
+    {
+        return Ok(());
+    }
+    let mut updated = store.clone();
+    if state == ConsentState::Unknown {
+        updated.remove(key);
+    } else {
+        let changed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        updated.insert(key.to_string(), ConsentRecord { state, changed_at });
+    }
+    let path = consent_path()?;
+    let text = ron::ser::to_string_pretty(&updated, ron::ser::PrettyConfig::default())
+        .map_err(|e| PathIoError::Format(path.clone(), e.to_string()))?;
+    io::write_atomic(&path, text.as_bytes())?;
+    *store = updated;
+    PENDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(ConsentChanged {
+            key: key.to_string(),
+            state,
+        });
+    Ok(())
+}
+
+/// The path of the consent file.
+pub fn consent_path() -> Result<PathBuf, PathIoError> {
+    let dir = *CONSENT_DIR.read().unwrap_or_else(|e| e.into_inner());
+    Ok(match dir {
+        Some(dir) => dir()?.join(CONSENT_FILE_NAME),
+        None => PathResolver::determine_base_path(None)?.join(CONSENT_FILE),
+    })
+}
+
+fn loaded(guard: &mut Option<ConsentStore>) -> Result<&mut ConsentStore, PathIoError> {
+    if guard.is_none() {
+        let path = consent_path()?;
+        let store = match fs::read_to_string(&path) {
+            Ok(text) => {
+                ron::from_str(&text).map_err(|e| PathIoError::Format(path, e.to_string()))?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => ConsentStore::new(),
+            Err(e) => return Err(PathIoError::Io(path, e)),
+        };
+        *guard = Some(store);
+    }
+    Ok(guard.get_or_insert_default())
+}
+
+pub(crate) fn publish_consent_changes(mut messages: MessageWriter<ConsentChanged>) {
+    let pending = std::mem::take(&mut *PENDING.lock().unwrap_or_else(|e| e.into_inner()));
+    messages.write_batch(pending);
+}
@@ -0,0 +1,131 @@
+//! Coalescing of rapid successive saves of the same file.
+//!
+//! Settings sliders or window resizes can request a save every frame. [`debounced_save`]
+//! serializes the data right away but only writes the latest version once the file has
+//! been quiet for the given period. [`PathsPlugin`](crate::PathsPlugin) writes due saves
+//! every frame and flushes all pending saves on [`AppExit`].
+
+use {
+    crate::{TypedPath, display::redact, io, io::PathIoError},
+    bevy_app::AppExit,
+    bevy_ecs::prelude::*,
+    bevy_log::error,
+    serde::Serialize,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+/// How many quiet periods a save may be postponed at most while changes keep coming.
+pub const MAX_POSTPONED_PERIODS: u32 = 4;
+
+type WriteFn = Box<dyn Fn() -> Result<(), PathIoError> + Send>;
+
+struct PendingSave {
+    first_request: Instant,
+    due: Instant,
+    quiet: Duration,
+    write: WriteFn,
+}
+
+static PENDING: Mutex<Option<HashMap<PathBuf, PendingSave>>> = Mutex::new(None);
+
+/// Saves `data` as a RON file `sub_path` inside `marker` once no further save of the same
+/// file was requested for `quiet`.
+///
+/// The data is serialized immediately, so later changes to `data` are not picked up.
+/// While saves keep coming, the file is still written every
+/// [`MAX_POSTPONED_PERIODS`] × `quiet`, so the latest state never stays unsaved for long.
+/// A failed write is retried after another `quiet` period, unless a newer save of the same
+/// file was requested in the meantime.
+pub fn debounced_save<P: TypedPath + Clone, D: Serialize>(
+    marker: &P,
+    sub_path: impl AsRef<Path>,
+    data: &D,
+    quiet: Duration,
+) -> Result<(), PathIoError> {
+    let sub_path = sub_path.as_ref().to_path_buf();
+    let path = io::resolve_in(marker, &sub_path)?;
+    let text = ron::ser::to_string_pretty(data, ron::ser::PrettyConfig::default())
+        .map_err(|e| PathIoError::Format(path.clone(), e.to_string()))?;
+    let marker = marker.clone();
+    let write: WriteFn = Box::new(move || io::write(&marker, &sub_path, text.as_bytes()));
+
+    let now = Instant::now();
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    let pending = pending.get_or_insert_default();
+    let first_request = pending.get(&path).map_or(now, |save| save.first_request);
+    let due = (now + quiet).min(first_request + quiet * MAX_POSTPONED_PERIODS);
+    pending.insert(
+        path,
+        PendingSave {
+            first_request,
+            due,
+            quiet,
+            write,
+        },
+    );
+    Ok(())
+}
+
+/// Returns `true` if saves are waiting for their quiet period.
+pub fn has_pending_saves() -> bool {
+    PENDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .is_some_and(|pending| !pending.is_empty())
+}
+
+/// Writes all pending saves immediately. Returns the first error; all saves are attempted.
+pub fn flush_debounced_saves() -> Result<(), PathIoError> {
+    write_due(None)
+}
+
+/// Writes the saves due at `now`, or all of them if `now` is `None`.
+///
+/// Failed saves are queued again, unless a newer save of the same file arrived meanwhile.
+fn write_due(now: Option<Instant>) -> Result<(), PathIoError> {
+    let due: Vec<(PathBuf, PendingSave)> = {
+        let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(pending) = pending.as_mut() else {
+            return Ok(());
+        };
+        let paths: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, save)| now.is_none_or(|now| save.due <= now))
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths
+            .into_iter()
+            .filter_map(|path| pending.remove(&path).map(|save| (path, save)))
+            .collect()
+    };
+    let mut result = Ok(());
+    for (path, mut save) in due {
+        let Err(e) = (save.write)() else {
+            continue;
+        };
+        if result.is_ok() {
+            result = Err(e);
+        }
+        save.due = Instant::now() + save.quiet;
+        PENDING
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_default()
+            .entry(path)
+            .or_insert(save);
+    }
+    result
+}
+
+pub(crate) fn write_debounced_saves(mut exit: MessageReader<AppExit>) {
+    let now = (exit.read().count() == 0).then(Instant::now);
+    if let Err(e) = write_due(now) {
+        error!("Failed to write debounced save: {}", redact(&e.to_string()));
+    }
+}
@@ -0,0 +1,117 @@
+//! Saves consisting of a whole directory, committed with an atomic swap.
+//!
+//! Files are written into `<slot>.tmp/` (see [`begin_dir_save`]). [`commit_dir_save`] then
+//! renames the current `<slot>/` to `<slot>.bak/`, moves `<slot>.tmp/` into place and removes
+//! the backup. If the process crashes in between, the swap is completed by
+//! [`recovery`](crate::recovery) on the next start, or [`recover_dir_save`] restores the
+//! previous save before the next one begins, so a multi-file save is never observed
+//! half-updated.
+
+use {
+    crate::{
+        TypedPath,
+        display::RedactedPath,
+        external, io,
+        io::PathIoError,
+        recovery::{self, Intent},
+        registration,
+    },
+    bevy_log::info_span,
+    std::{
+        any::type_name,
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Paths of the slot directory, the staging directory and the backup directory.
+fn slot_dirs<P: TypedPath>(marker: &P, slot: &Path) -> Result<[PathBuf; 3], PathIoError> {
+    let with_suffix = |suffix: &str| {
+        let mut dir = slot.to_path_buf();
+        dir.as_mut_os_string().push(suffix);
+        dir
+    };
+    Ok([
+        io::resolve_in(marker, slot)?,
+        io::resolve_in(marker, with_suffix(".tmp"))?,
+        io::resolve_in(marker, with_suffix(".bak"))?,
+    ])
+}
+
+/// Prepares an empty staging directory for `slot` inside `marker` and returns its path.
+///
+/// Leftovers of an earlier, uncommitted save are removed.
+pub fn begin_dir_save<P: TypedPath>(
+    marker: &P,
+    slot: impl AsRef<Path>,
+) -> Result<PathBuf, PathIoError> {
+    let slot = slot.as_ref();
+    let [current, staging, _] = slot_dirs(marker, slot)?;
+    registration::check_writable::<P>(&current)?;
+    recover_dir_save(marker, slot)?;
+    if staging.exists() {
+        external::owned_write(&[&staging], || fs::remove_dir_all(&staging))
+            .map_err(|e| PathIoError::Io(staging.clone(), e))?;
+    }
+    fs::create_dir_all(&staging).map_err(|e| PathIoError::Io(staging.clone(), e))?;
+    Ok(staging)
+}
+
+/// Replaces `slot` with the content of its staging directory and returns the slot path.
+pub fn commit_dir_save<P: TypedPath>(
+    marker: &P,
+    slot: impl AsRef<Path>,
+) -> Result<PathBuf, PathIoError> {
+    let [current, staging, backup] = slot_dirs(marker, slot.as_ref())?;
+    let _span = info_span!(
+        "bevy_paths::commit_dir_save",
+        marker = type_name::<P>(),
+        path = %RedactedPath(&current)
+    )
+    .entered();
+    if !staging.is_dir() {
+        return Err(PathIoError::Io(
+            staging,
+            std::io::ErrorKind::NotFound.into(),
+        ));
+    }
+    let intent = Intent::DirSwap {
+        current: current.clone(),
+        staging: staging.clone(),
+        backup: backup.clone(),
+    };
+    recovery::guarded(intent, || {
+        external::owned_write(&[&current, &staging, &backup], || {
+            if current.exists() {
+                fs::rename(&current, &backup).map_err(|e| PathIoError::Io(backup.clone(), e))?;
+            }
+            fs::rename(&staging, &current).map_err(|e| PathIoError::Io(current.clone(), e))?;
+            if backup.exists() {
+                fs::remove_dir_all(&backup).map_err(|e| PathIoError::Io(backup.clone(), e))?;
+            }
+            Ok(())
+        })
+    })?;
+    Ok(current)
+}
+
+/// Restores a consistent state for `slot` after an interrupted [`commit_dir_save`].
+///
+/// - Slot missing, backup present: the swap was interrupted, the backup is restored.
+/// - Slot and backup present: the swap completed, the backup is removed.
+pub fn recover_dir_save<P: TypedPath>(
+    marker: &P,
+    slot: impl AsRef<Path>,
+) -> Result<(), PathIoError> {
+    let [current, _, backup] = slot_dirs(marker, slot.as_ref())?;
+    if !backup.exists() {
+        return Ok(());
+    }
+    external::owned_write(&[&current, &backup], || {
+        if current.exists() {
+            fs::remove_dir_all(&backup).map_err(|e| PathIoError::Io(backup.clone(), e))
+        } else {
+            fs::rename(&backup, &current).map_err(|e| PathIoError::Io(current.clone(), e))
+        }
+    })
+}
@@ -0,0 +1,145 @@
+//! Player-facing formatting and redaction of resolved paths.
+
+use std::{
+    env, fmt,
+    path::{MAIN_SEPARATOR, Path},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Formats `path` for settings screens and log lines shown to players.
+///
+/// - The user's home directory is abbreviated to `~`.
+/// - Separators are normalized for the current platform and `\\?\` prefixes are removed.
+/// - With `max_width`, the middle is replaced by `…` so at most `max_width` characters remain.
+pub fn display_path(path: &Path, max_width: Option<usize>) -> String {
+    let home = env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" });
+    format_path(path, home.as_deref().map(Path::new), max_width)
+}
+
+pub(crate) fn format_path(path: &Path, home: Option<&Path>, max_width: Option<usize>) -> String {
+    let text = path.to_string_lossy();
+    let mut text = text.strip_prefix(r"\\?\").unwrap_or(&text).to_string();
+    if cfg!(windows) {
+        text = text.replace('/', "\\");
+    }
+    if let Some(home) = home.map(|home| home.to_string_lossy())
+        && !home.is_empty()
+        && let Some(rest) = text.strip_prefix(home.as_ref())
+        && (rest.is_empty() || rest.starts_with(MAIN_SEPARATOR))
+    {
+        text = format!("~{rest}");
+    }
+    match max_width {
+        Some(max) => truncate_middle(&text, max),
+        None => text,
+    }
+}
+
+fn truncate_middle(text: &str, max: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= max {
+        return text.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+    let tail = (max - 1) / 2;
+    let head = max - 1 - tail;
+    let mut result: String = chars[..head].iter().collect();
+    result.push('…');
+    result.extend(&chars[chars.len() - tail..]);
+    result
+}
+
+/// Replaces the home directory in redacted text.
+pub const HOME_PLACEHOLDER: &str = "<home>";
+
+/// Replaces the user name in redacted text.
+pub const USER_PLACEHOLDER: &str = "<user>";
+
+static PRIVACY_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables privacy mode.
+///
+/// In privacy mode the crate's own log output passes through [`redact`], so shared logs
+/// and bug reports do not reveal the player's home directory or user name.
+pub fn set_privacy_mode(enabled: bool) {
+    PRIVACY_MODE.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns `true` if privacy mode is enabled.
+pub fn privacy_mode() -> bool {
+    PRIVACY_MODE.load(Ordering::Relaxed)
+}
+
+/// Redacts the home directory and user name in `text` if privacy mode is enabled.
+///
+/// Use this for your own log lines that contain paths.
+pub fn redact(text: &str) -> String {
+    if !privacy_mode() {
+        return text.to_string();
+    }
+    let home = env::var(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).ok();
+    let user = env::var(if cfg!(windows) { "USERNAME" } else { "USER" }).ok();
+    redact_with(text, home.as_deref(), user.as_deref())
+}
+
+/// Redacts `path` like [`redact`].
+pub fn redact_path(path: &Path) -> String {
+    redact(&path.display().to_string())
+}
+
+/// Displays a path redacted like [`redact_path`].
+///
+/// Redaction happens only when the value is formatted, so use this for `tracing` span
+/// fields, which are often not recorded at all.
+pub struct RedactedPath<'a>(pub &'a Path);
+
+impl fmt::Display for RedactedPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&redact_path(self.0))
+    }
+}
+
+pub(crate) fn redact_with(text: &str, home: Option<&str>, user: Option<&str>) -> String {
+    let mut text = text.to_string();
+    // The home directory is only replaced where it is a whole path, so `/home/anna` does not
+    // match inside `/home/annabelle` or `/backup/home/anna`.
+    if let Some(home) = home.filter(|home| !home.is_empty()) {
+        text = replace_paths(&text, home, HOME_PLACEHOLDER);
+    }
+    // The user name is only replaced as a whole path component, e.g. `/Users/<user>/`.
+    if let Some(user) = user.filter(|user| !user.is_empty()) {
+        for separator in ['/', '\\'] {
+            text = text.replace(
+                &format!("{separator}{user}{separator}"),
+                &format!("{separator}{USER_PLACEHOLDER}{separator}"),
+            );
+            if let Some(prefix) = text.strip_suffix(&format!("{separator}{user}")) {
+                text = format!("{prefix}{separator}{USER_PLACEHOLDER}");
+            }
+        }
+    }
+    text
+}
+
+/// Replaces `path` in `text` where it is not preceded or followed by more of a path component.
+fn replace_paths(text: &str, path: &str, replacement: &str) -> String {
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let mut result = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (start, _) in text.match_indices(path) {
+        let end = start + path.len();
+        if start < copied
+            || text[..start].chars().next_back().is_some_and(is_name_char)
+            || text[end..].chars().next().is_some_and(is_name_char)
+        {
+            continue;
+        }
+        result.push_str(&text[copied..start]);
+        result.push_str(replacement);
+        copied = end;
+    }
+    result.push_str(&text[copied..]);
+    result
+}
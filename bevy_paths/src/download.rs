@@ -0,0 +1,183 @@
+//! Staged downloads with verification and atomic finalize.
+//!
+//! Content is streamed into `downloads/partial/<name>.part` inside a marker. Partial files
+//! survive restarts, so a download can be resumed from [`StagedDownload::resume_offset`].
+//! Once complete, [`StagedDownload::finalize`] verifies size and hash and moves the file
+//! to its final location with a single rename. A verified file whose move was interrupted is
+//! moved by [`recovery`](crate::recovery) on the next start.
+
+use {
+    crate::{
+        TypedPath,
+        display::RedactedPath,
+        external,
+        external::AppendGuard,
+        io,
+        io::PathIoError,
+        io_stats,
+        recovery::{self, Intent},
+        registration,
+    },
+    bevy_log::{info_span, trace_span},
+    std::{
+        fs::{self, File, OpenOptions},
+        io::{Read, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// The directory inside a marker where partial downloads are staged.
+pub const PARTIAL_DIR: &str = "downloads/partial";
+
+/// The expected properties of a completed download.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadVerification {
+    /// The expected size in bytes.
+    pub size: Option<u64>,
+    /// The expected Blake3 hash as a hex string.
+    pub blake3: Option<String>,
+}
+
+/// A download in progress, staged inside the marker `P`.
+pub struct StagedDownload<P: TypedPath> {
+    marker: P,
+    path: PathBuf,
+    file: File,
+    written: u64,
+    owned: AppendGuard,
+}
+
+impl<P: TypedPath> StagedDownload<P> {
+    /// Opens the partial file for `name`, keeping any data from a previous session.
+    pub fn open(marker: P, name: impl AsRef<Path>) -> Result<Self, PathIoError> {
+        let mut partial = Path::new(PARTIAL_DIR).join(name);
+        partial.as_mut_os_string().push(".part");
+        let path = io::resolve_in(&marker, partial)?;
+        registration::check_writable::<P>(&path)?;
+        let _span =
+            info_span!("bevy_paths::download", op = "open", path = %RedactedPath(&path)).entered();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| PathIoError::Io(parent.to_path_buf(), e))?;
+        }
+        let owned = AppendGuard::new(&path);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| PathIoError::Io(path.clone(), e))?;
+        let written = file
+            .metadata()
+            .map_err(|e| PathIoError::Io(path.clone(), e))?
+            .len();
+        Ok(Self {
+            marker,
+            path,
+            file,
+            written,
+            owned,
+        })
+    }
+
+    /// The number of bytes already staged. Resume the transfer from this offset.
+    pub fn resume_offset(&self) -> u64 {
+        self.written
+    }
+
+    /// Appends a chunk of downloaded data.
+    pub fn write(&mut self, chunk: &[u8]) -> Result<(), PathIoError> {
+        let _span = trace_span!(
+            "bevy_paths::download",
+            op = "write",
+            path = %RedactedPath(&self.path),
+            bytes = chunk.len()
+        )
+        .entered();
+        self.file
+            .write_all(chunk)
+            .map_err(|e| PathIoError::Io(self.path.clone(), e))?;
+        self.written += chunk.len() as u64;
+        io_stats::record_write::<P>(chunk.len());
+        Ok(())
+    }
+
+    /// Verifies the staged file and moves it to `destination` inside the marker.
+    ///
+    /// If verification fails, the partial file is deleted so the next attempt starts over.
+    pub fn finalize(
+        self,
+        destination: impl AsRef<Path>,
+        verification: &DownloadVerification,
+    ) -> Result<PathBuf, PathIoError> {
+        let target = io::resolve_in(&self.marker, destination)?;
+        let _span = info_span!(
+            "bevy_paths::download",
+            op = "finalize",
+            path = %RedactedPath(&self.path),
+            target = %RedactedPath(&target)
+        )
+        .entered();
+        self.file
+            .sync_all()
+            .map_err(|e| PathIoError::Io(self.path.clone(), e))?;
+        drop(self.file);
+        drop(self.owned);
+
+        if verification.blake3.is_some() {
+            io_stats::record_read::<P>(self.written as usize);
+        }
+        if let Err(reason) = verify(&self.path, self.written, verification) {
+            let _ = external::owned_write(&[&self.path], || fs::remove_file(&self.path));
+            return Err(PathIoError::VerificationFailed(self.path, reason));
+        }
+
+        let intent = Intent::DownloadFinalize {
+            partial: self.path.clone(),
+            target: target.clone(),
+        };
+        recovery::guarded(intent, || {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| PathIoError::Io(parent.to_path_buf(), e))?;
+            }
+            external::owned_write(&[&self.path, &target], || fs::rename(&self.path, &target))
+                .map_err(|e| PathIoError::Io(target.clone(), e))
+        })?;
+        Ok(target)
+    }
+
+    /// Discards the download and deletes its partial file.
+    pub fn cancel(self) -> Result<(), PathIoError> {
+        drop(self.file);
+        drop(self.owned);
+        external::owned_write(&[&self.path], || fs::remove_file(&self.path))
+            .map_err(|e| PathIoError::Io(self.path, e))
+    }
+}
+
+fn verify(path: &Path, written: u64, verification: &DownloadVerification) -> Result<(), String> {
+    if let Some(size) = verification.size
+        && size != written
+    {
+        return Err(format!("expected {size} bytes, got {written}"));
+    }
+    if let Some(expected) = &verification.blake3 {
+        let actual = hash_file(path).map_err(|e| e.to_string())?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!("expected Blake3 hash {expected}, got {actual}"));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
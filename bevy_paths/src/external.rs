@@ -0,0 +1,256 @@
+//! Notifications when files owned by the process are changed from outside.
+//!
+//! Every file written by the crate's helpers ([`io::write_atomic`](crate::io::write_atomic)
+//! and everything built on it, journals, the file handle pool, recordings, downloads and
+//! directory saves) is remembered with its size and modification time. Markers registered
+//! with [`ExternalModificationExt::detect_external_modifications`] are polled and a
+//! [`FileExternallyModified`] message is written when such a file changed without going
+//! through the helpers (another process, a text editor, cloud sync).
+//!
+//! Files that are open for appending are not checked until they are closed. At most
+//! [`MAX_OWNED_FILES`] files are remembered; the ones written least recently are forgotten
+//! first. Until a marker is registered, nothing is recorded, so writes pay nothing for it.
+
+use {
+    crate::{TypedPath, display::redact_path, io},
+    bevy_app::{App, Update},
+    bevy_ecs::prelude::*,
+    bevy_log::{info_span, warn},
+    std::{
+        any::type_name,
+        collections::{BTreeMap, BTreeSet},
+        fs,
+        marker::PhantomData,
+        path::{Path, PathBuf},
+        sync::{
+            Mutex, MutexGuard,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+/// How often registered markers are checked for external modifications.
+pub const EXTERNAL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A file inside marker `T` was changed outside of the IO helpers.
+#[derive(Message, Debug, Clone)]
+pub struct FileExternallyModified<T: TypedPath> {
+    /// The modified file. It may have been deleted.
+    pub path: PathBuf,
+    _marker: PhantomData<fn() -> T>,
+}
+
+/// The maximum number of files remembered as written by this process.
+pub const MAX_OWNED_FILES: usize = 4096;
+
+type Fingerprint = Option<(u64, Option<SystemTime>)>;
+
+struct OwnedFile {
+    fingerprint: Fingerprint,
+    /// When the file was last recorded, as a key of [`OwnedFiles::by_age`].
+    recorded: u64,
+    /// The number of open append handles, see [`AppendGuard`].
+    appenders: usize,
+}
+
+/// The files written by this process.
+#[derive(Default)]
+struct OwnedFiles {
+    /// Ordered by path, so the files below a directory form one range.
+    files: BTreeMap<PathBuf, OwnedFile>,
+    /// The paths in the order they were recorded, for forgetting the oldest ones.
+    by_age: BTreeMap<u64, PathBuf>,
+    next_record: u64,
+}
+
+static OWNED: Mutex<Option<OwnedFiles>> = Mutex::new(None);
+
+/// The number of markers registered with
+/// [`ExternalModificationExt::detect_external_modifications`]. Nothing is recorded without one.
+static WATCHED: AtomicUsize = AtomicUsize::new(0);
+
+fn owned() -> MutexGuard<'static, Option<OwnedFiles>> {
+    OWNED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn is_watching() -> bool {
+    WATCHED.load(Ordering::Relaxed) > 0
+}
+
+/// Remembers the current state of `path` as written by this process.
+pub(crate) fn record_owned(path: &Path) {
+    if is_watching() {
+        owned().get_or_insert_default().refresh(path);
+    }
+}
+
+/// Runs `write`, which changes the files at or below `paths`, and remembers their new state.
+///
+/// The poll waits until the state is recorded, so it never sees the change as external.
+pub(crate) fn owned_write<T>(paths: &[&Path], write: impl FnOnce() -> T) -> T {
+    if !is_watching() {
+        return write();
+    }
+    let mut owned = owned();
+    let result = write();
+    let owned = owned.get_or_insert_default();
+    for path in paths {
+        owned.refresh(path);
+    }
+    result
+}
+
+impl OwnedFiles {
+    /// Records the state of `path`, or of all files below it if it is a directory.
+    fn refresh(&mut self, path: &Path) {
+        let mut paths: BTreeSet<PathBuf> = self
+            .files
+            .range(path.to_path_buf()..)
+            .map(|(known, _)| known)
+            .take_while(|known| known.starts_with(path))
+            .cloned()
+            .collect();
+        match fs::symlink_metadata(path) {
+            Ok(meta) if meta.is_dir() => paths.extend(io::walk_files(path).unwrap_or_default()),
+            _ => {
+                paths.insert(path.to_path_buf());
+            }
+        }
+        for path in paths {
+            self.record(path);
+        }
+        self.evict();
+    }
+
+    fn record(&mut self, path: PathBuf) {
+        let fingerprint = fingerprint(&path);
+        let recorded = self.next_record;
+        self.next_record += 1;
+        self.by_age.insert(recorded, path.clone());
+        match self.files.get_mut(&path) {
+            Some(file) => {
+                self.by_age.remove(&file.recorded);
+                file.fingerprint = fingerprint;
+                file.recorded = recorded;
+            }
+            None => {
+                self.files.insert(
+                    path,
+                    OwnedFile {
+                        fingerprint,
+                        recorded,
+                        appenders: 0,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Forgets the files recorded least recently beyond [`MAX_OWNED_FILES`], except open ones.
+    fn evict(&mut self) {
+        let excess = self.files.len().saturating_sub(MAX_OWNED_FILES);
+        if excess == 0 {
+            return;
+        }
+        let oldest: Vec<(u64, PathBuf)> = self
+            .by_age
+            .iter()
+            .filter(|(_, path)| self.files.get(*path).is_some_and(|f| f.appenders == 0))
+            .take(excess)
+            .map(|(recorded, path)| (*recorded, path.clone()))
+            .collect();
+        for (recorded, path) in oldest {
+            self.by_age.remove(&recorded);
+            self.files.remove(&path);
+        }
+    }
+}
+
+/// Marks a file as open for appending by this process until dropped.
+///
+/// Appends are not reported as external modifications. On drop the state of the file is
+/// recorded, so keep the guard alive until the file is flushed.
+pub(crate) struct AppendGuard(PathBuf);
+
+impl AppendGuard {
+    pub(crate) fn new(path: &Path) -> Self {
+        if is_watching() {
+            let mut owned = owned();
+            let owned = owned.get_or_insert_default();
+            owned.refresh(path);
+            if let Some(file) = owned.files.get_mut(path) {
+                file.appenders += 1;
+            }
+        }
+        Self(path.to_path_buf())
+    }
+}
+
+impl Drop for AppendGuard {
+    fn drop(&mut self) {
+        if !is_watching() {
+            return;
+        }
+        let mut owned = owned();
+        let owned = owned.get_or_insert_default();
+        if let Some(file) = owned.files.get_mut(&self.0) {
+            file.appenders = file.appenders.saturating_sub(1);
+        }
+        owned.refresh(&self.0);
+    }
+}
+
+fn fingerprint(path: &Path) -> Fingerprint {
+    fs::metadata(path)
+        .ok()
+        .map(|meta| (meta.len(), meta.modified().ok()))
+}
+
+/// Extension trait for watching markers for external modifications.
+pub trait ExternalModificationExt {
+    /// Polls the owned files inside `T` every [`EXTERNAL_POLL_INTERVAL`].
+    fn detect_external_modifications<T: TypedPath + Default>(&mut self) -> &mut Self;
+}
+
+impl ExternalModificationExt for App {
+    fn detect_external_modifications<T: TypedPath + Default>(&mut self) -> &mut Self {
+        WATCHED.fetch_add(1, Ordering::Relaxed);
+        self.add_message::<FileExternallyModified<T>>()
+            .add_systems(Update, poll_external_modifications::<T>)
+    }
+}
+
+fn poll_external_modifications<T: TypedPath + Default>(
+    mut last_poll: Local<Option<Instant>>,
+    mut messages: MessageWriter<FileExternallyModified<T>>,
+) {
+    if last_poll.is_some_and(|last| last.elapsed() < EXTERNAL_POLL_INTERVAL) {
+        return;
+    }
+    *last_poll = Some(Instant::now());
+    let _span = info_span!("bevy_paths::external_poll", marker = type_name::<T>()).entered();
+    let Ok(root) = T::default().resolve() else {
+        return;
+    };
+    let mut owned = owned();
+    let Some(owned) = owned.as_mut() else {
+        return;
+    };
+    let files = owned
+        .files
+        .range_mut(root.clone()..)
+        .take_while(|(path, _)| path.starts_with(&root))
+        .filter(|(_, file)| file.appenders == 0);
+    for (path, file) in files {
+        let current = fingerprint(path);
+        if current != file.fingerprint {
+            file.fingerprint = current;
+            warn!("File '{}' was modified externally.", redact_path(path));
+            messages.write(FileExternallyModified {
+                path: path.clone(),
+                _marker: PhantomData,
+            });
+        }
+    }
+}
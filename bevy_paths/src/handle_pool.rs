@@ -0,0 +1,169 @@
+//! A cache of open file handles for append-heavy files.
+//!
+//! Logs, journals and telemetry are appended to many times per second. Instead of reopening
+//! the file on every call, [`FileHandlePool`] keeps up to `capacity` buffered handles open,
+//! evicting the least recently used one. All handles are flushed on [`AppExit`].
+
+use {
+    crate::{
+        TypedPath,
+        display::{RedactedPath, redact},
+        external::AppendGuard,
+        io,
+        io::PathIoError,
+        io_stats, registration,
+    },
+    bevy_app::{App, AppExit, Last},
+    bevy_ecs::prelude::*,
+    bevy_log::{debug_span, error, trace_span},
+    std::{
+        fs::{self, File, OpenOptions},
+        io::{BufWriter, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// The default number of handles kept open.
+pub const DEFAULT_HANDLE_CAPACITY: usize = 16;
+
+/// Open, buffered append handles keyed by resolved path.
+#[derive(Resource)]
+pub struct FileHandlePool {
+    capacity: usize,
+    /// Least recently used first. The guard is dropped after the writer is flushed.
+    handles: Vec<(PathBuf, BufWriter<File>, AppendGuard)>,
+}
+
+impl Default for FileHandlePool {
+    fn default() -> Self {
+        Self::new(DEFAULT_HANDLE_CAPACITY)
+    }
+}
+
+impl FileHandlePool {
+    /// Creates a pool keeping at most `capacity` handles open.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            handles: Vec::new(),
+        }
+    }
+
+    /// Appends `bytes` to the file `sub_path` inside `marker`.
+    pub fn append<P: TypedPath>(
+        &mut self,
+        marker: &P,
+        sub_path: impl AsRef<Path>,
+        bytes: &[u8],
+    ) -> Result<(), PathIoError> {
+        let path = io::resolve_in(marker, sub_path)?;
+        registration::check_writable::<P>(&path)?;
+        self.append_to(&path, bytes)?;
+        io_stats::record_write::<P>(bytes.len());
+        Ok(())
+    }
+
+    /// Appends `bytes` to an already resolved `path`.
+    ///
+    /// Without a marker type, the write is not counted in [`io_stats`](crate::io_stats).
+    pub fn append_to(&mut self, path: &Path, bytes: &[u8]) -> Result<(), PathIoError> {
+        let _span = trace_span!(
+            "bevy_paths::handle_pool",
+            op = "append",
+            path = %RedactedPath(path),
+            bytes = bytes.len()
+        )
+        .entered();
+        let index = match self.handles.iter().position(|(p, ..)| p == path) {
+            Some(index) => index,
+            None => self.open(path)?,
+        };
+        // Move to the most recently used position.
+        let entry = self.handles.remove(index);
+        self.handles.push(entry);
+        let (_, writer, _) = self.handles.last_mut().expect("entry was just pushed");
+        writer
+            .write_all(bytes)
+            .map_err(|e| PathIoError::Io(path.to_path_buf(), e))
+    }
+
+    /// The number of currently open handles.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Returns `true` if no handle is open.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Flushes all open handles.
+    pub fn flush(&mut self) -> Result<(), PathIoError> {
+        let _span = debug_span!(
+            "bevy_paths::handle_pool",
+            op = "flush",
+            handles = self.len()
+        )
+        .entered();
+        for (path, writer, _) in &mut self.handles {
+            writer
+                .flush()
+                .map_err(|e| PathIoError::Io(path.clone(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Flushes and closes the handle for `path`, if open.
+    pub fn close(&mut self, path: &Path) -> Result<(), PathIoError> {
+        if let Some(index) = self.handles.iter().position(|(p, ..)| p == path) {
+            let (path, mut writer, _owned) = self.handles.remove(index);
+            writer.flush().map_err(|e| PathIoError::Io(path, e))?;
+        }
+        Ok(())
+    }
+
+    fn open(&mut self, path: &Path) -> Result<usize, PathIoError> {
+        let _span = debug_span!("bevy_paths::handle_pool", op = "open", path = %RedactedPath(path))
+            .entered();
+        if self.handles.len() >= self.capacity {
+            let (evicted, mut writer, _owned) = self.handles.remove(0);
+            writer.flush().map_err(|e| PathIoError::Io(evicted, e))?;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| PathIoError::Io(parent.to_path_buf(), e))?;
+        }
+        let owned = AppendGuard::new(path);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| PathIoError::Io(path.to_path_buf(), e))?;
+        self.handles
+            .push((path.to_path_buf(), BufWriter::new(file), owned));
+        Ok(self.handles.len() - 1)
+    }
+}
+
+/// Extension trait for enabling the [`FileHandlePool`].
+pub trait FileHandlePoolExt {
+    /// Inserts a [`FileHandlePool`] with `capacity` handles, flushed on [`AppExit`].
+    fn add_file_handle_pool(&mut self, capacity: usize) -> &mut Self;
+}
+
+impl FileHandlePoolExt for App {
+    fn add_file_handle_pool(&mut self, capacity: usize) -> &mut Self {
+        self.insert_resource(FileHandlePool::new(capacity))
+            .add_systems(Last, flush_on_exit)
+    }
+}
+
+fn flush_on_exit(mut pool: ResMut<FileHandlePool>, mut exit: MessageReader<AppExit>) {
+    if exit.read().count() > 0
+        && let Err(e) = pool.flush()
+    {
+        error!(
+            "Failed to flush file handles on exit: {}",
+            redact(&e.to_string())
+        );
+    }
+}
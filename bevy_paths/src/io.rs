@@ -0,0 +1,516 @@
+//! Safe file IO on top of [`TypedPath`] locations.
+//!
+//! Every helper resolves the marker first and validates the sub path with the same rules
+//! as `#[file(...)]` templates, so a file can never escape its marker directory.
+//! Writes are atomic: data goes into a sibling `.tmp` file which is synced and then renamed.
+
+use {
+    crate::{
+        PathValidationError, TypedPath, conflict, display::RedactedPath, external, io_stats,
+        long_names, migration, normalize_component, overlay, private::PathResolver, profile,
+        progress::ProgressHandle, registration, sort, sync_state, validate_component,
+        validate_resolved_path_with,
+    },
+    bevy_log::{
+        info_span,
+        tracing::{field, span::EnteredSpan},
+    },
+    serde::{Serialize, de::DeserializeOwned},
+    std::{
+        any::type_name,
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Errors returned by the IO helpers.
+#[derive(Debug, thiserror::Error)]
+pub enum PathIoError {
+    /// The marker or the sub path failed validation.
+    ///
+    /// # Recovery
+    /// See [`PathValidationError`].
+    #[error(transparent)]
+    Validation(#[from] PathValidationError),
+
+    /// A filesystem operation failed.
+    ///
+    /// # Recovery
+    /// Check file permissions, disk space, or whether the file exists.
+    #[error("IO operation on '{0}' failed. IO Error: {1}")]
+    Io(PathBuf, io::Error),
+
+    /// The file content could not be serialized or deserialized.
+    ///
+    /// # Recovery
+    /// Check that the file matches the expected format.
+    #[error("Failed to (de)serialize '{0}': {1}")]
+    Format(PathBuf, String),
+
+    /// A file did not match its expected size or hash.
+    ///
+    /// # Recovery
+    /// Fetch or write the file again.
+    #[error("Verification of '{0}' failed: {1}")]
+    VerificationFailed(PathBuf, String),
+
+    /// The operation was cancelled through its [`ProgressHandle`].
+    ///
+    /// # Recovery
+    /// Start the operation again. Partially written data may remain at the destination.
+    #[error("The operation was cancelled.")]
+    Cancelled,
+
+    /// The base path cannot be moved to the given location.
+    ///
+    /// # Recovery
+    /// Choose an empty directory outside of the current base path, on a volume with enough
+    /// free space.
+    #[error("Cannot move the base path to '{0}': {1}")]
+    RelocationRejected(PathBuf, String),
+
+    /// The marker was registered as read-only.
+    ///
+    /// # Recovery
+    /// Write to another marker, or register this one with
+    /// [`readonly(false)`](crate::RegistrationOptions::readonly).
+    #[error("Cannot write '{0}': the directory is read-only.")]
+    ReadOnly(PathBuf),
+
+    /// The write would grow the marker directory beyond its quota.
+    ///
+    /// # Recovery
+    /// Delete files inside the marker, or raise its
+    /// [`quota`](crate::RegistrationOptions::quota).
+    #[error("Cannot write '{0}': the directory would exceed its quota of {1} bytes.")]
+    QuotaExceeded(PathBuf, u64),
+
+    /// A path that must lie inside the base path points elsewhere.
+    ///
+    /// # Recovery
+    /// Only pass paths resolved through [`TypedPath`] markers.
+    #[error("The path '{0}' lies outside of the base path.")]
+    OutsideBasePath(PathBuf),
+}
+
+impl PathIoError {
+    /// Returns `true` if the error was caused by a missing file.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::Io(_, e) if e.kind() == io::ErrorKind::NotFound)
+    }
+}
+
+/// Resolves `sub_path` inside the directory of `marker`.
+///
+/// `sub_path` must be a relative path following the same rules as resolved templates.
+/// Non-UTF-8 paths are rejected with [`PathValidationError::NonUtf8Path`], and the
+/// [`Strict`](profile::ValidationProfile::Strict) profile also screens for confusables.
+/// [`PathLimits`](profile::PathLimits) apply to the marker directory and `sub_path` combined.
+/// Markers with `separators = "reject"` reject `\` instead of treating it as `/`.
+pub fn resolve_in<P: TypedPath>(
+    marker: &P,
+    sub_path: impl AsRef<Path>,
+) -> Result<PathBuf, PathIoError> {
+    let policy = PathResolver::separator_policy(P::REJECT_BACKSLASHES);
+    let relative = validate_resolved_path_with(sub_path, policy)?;
+    profile::screen(&relative)?;
+    let dir = marker.resolve()?;
+    if profile::has_limits() {
+        let base = PathResolver::determine_base_path(None)?;
+        profile::check_limits(&dir.strip_prefix(&base).unwrap_or(&dir).join(&relative))?;
+    }
+    Ok(dir.join(relative))
+}
+
+/// Resolves `sub_path` like [`resolve_in`] and enters a tracing span carrying the
+/// operation, the marker type and the resolved path.
+fn traced<P: TypedPath>(
+    op: &'static str,
+    marker: &P,
+    sub_path: &Path,
+) -> Result<(PathBuf, EnteredSpan), PathIoError> {
+    let span = info_span!(
+        "bevy_paths::io",
+        op,
+        marker = type_name::<P>(),
+        path = field::Empty
+    )
+    .entered();
+    let path = resolve_in(marker, sub_path)?;
+    span.record("path", field::display(RedactedPath(&path)));
+    Ok((path, span))
+}
+
+/// Reads the file `sub_path` inside `marker`.
+pub fn read<P: TypedPath>(marker: &P, sub_path: impl AsRef<Path>) -> Result<Vec<u8>, PathIoError> {
+    let sub_path = sub_path.as_ref();
+    let (path, _span) = traced("read", marker, sub_path)?;
+    let path = overlay::read_path::<P>(path, sub_path)?;
+    let bytes = fs::read(&path).map_err(|e| PathIoError::Io(path, e))?;
+    io_stats::record_read::<P>(bytes.len());
+    Ok(bytes)
+}
+
+/// Atomically writes `bytes` to the file `sub_path` inside `marker`.
+///
+/// The file is recorded as modified for [`sync_state`](crate::sync_state) if `P` is tracked.
+pub fn write<P: TypedPath>(
+    marker: &P,
+    sub_path: impl AsRef<Path>,
+    bytes: &[u8],
+) -> Result<(), PathIoError> {
+    let sub_path = sub_path.as_ref();
+    let (path, _span) = traced("write", marker, sub_path)?;
+    registration::check_write(marker, &path, bytes.len() as u64)?;
+    write_atomic(&path, bytes)?;
+    io_stats::record_write::<P>(bytes.len());
+    written(marker, sub_path);
+    Ok(())
+}
+
+/// Maps the file `sub_path` inside `marker` into memory as a read-only view.
+///
+/// Use this for large baked data (nav meshes, world chunks) that should not be copied into RAM.
+///
+/// # Safety
+///
+/// The caller must ensure that the file is neither truncated nor modified, by this or any
+/// other process, while the returned map is alive. Managed directories can be changed by
+/// cloud sync clients and the player, so only map files the game ships or exclusively owns.
+#[cfg(feature = "memmap")]
+pub unsafe fn mmap<P: TypedPath>(
+    marker: &P,
+    sub_path: impl AsRef<Path>,
+) -> Result<memmap2::Mmap, PathIoError> {
+    let sub_path = sub_path.as_ref();
+    let (path, _span) = traced("mmap", marker, sub_path)?;
+    let path = overlay::read_path::<P>(path, sub_path)?;
+    let file = fs::File::open(&path).map_err(|e| PathIoError::Io(path.clone(), e))?;
+    // SAFETY: The caller guarantees that the file is not modified while the map is alive.
+    let map = unsafe { memmap2::Mmap::map(&file) }.map_err(|e| PathIoError::Io(path, e))?;
+    io_stats::record_read::<P>(map.len());
+    Ok(map)
+}
+
+/// Loads a RON file `sub_path` inside `marker`.
+///
+/// Migrations registered for `P` and `sub_path` with [`migration::register_migration`] are
+/// applied first.
+pub fn load_ron<P: TypedPath, D: DeserializeOwned>(
+    marker: &P,
+    sub_path: impl AsRef<Path>,
+) -> Result<D, PathIoError> {
+    let sub_path = sub_path.as_ref();
+    let (path, _span) = traced("load_ron", marker, sub_path)?;
+    let path = overlay::read_path::<P>(path, sub_path)?;
+    let text = fs::read_to_string(&path).map_err(|e| PathIoError::Io(path.clone(), e))?;
+    io_stats::record_read::<P>(text.len());
+    migration::deserialize::<P, D>(&path, sub_path, &text)
+}
+
+/// Atomically saves `data` as a RON file `sub_path` inside `marker`.
+///
+/// The file is recorded as modified for [`sync_state`](crate::sync_state) if `P` is tracked.
+pub fn save_ron<P: TypedPath, D: Serialize>(
+    marker: &P,
+    sub_path: impl AsRef<Path>,
+    data: &D,
+) -> Result<(), PathIoError> {
+    let sub_path = sub_path.as_ref();
+    let (path, _span) = traced("save_ron", marker, sub_path)?;
+    let text = ron::ser::to_string_pretty(data, ron::ser::PrettyConfig::default())
+        .map_err(|e| PathIoError::Format(path.clone(), e.to_string()))?;
+    registration::check_write(marker, &path, text.len() as u64)?;
+    write_atomic(&path, text.as_bytes())?;
+    io_stats::record_write::<P>(text.len());
+    written(marker, sub_path);
+    Ok(())
+}
+
+/// Copies the external file `source` into the directory of `marker`, e.g. for
+/// "import custom avatar" features.
+///
+/// The file name is made valid with [`sanitize_file_name`]. If the name is taken, a number
+/// is appended (`avatar (2).png`). Returns the final path relative to the marker directory.
+pub fn import_external_file<P: TypedPath>(
+    marker: &P,
+    source: &Path,
+) -> Result<String, PathIoError> {
+    let _span = info_span!(
+        "bevy_paths::io",
+        op = "import_external_file",
+        marker = type_name::<P>(),
+        source = %RedactedPath(source)
+    )
+    .entered();
+    let name = sanitize_file_name(&source.file_name().unwrap_or_default().to_string_lossy());
+    let dir = marker.resolve()?;
+    let size = fs::metadata(source)
+        .map_err(|e| PathIoError::Io(source.to_path_buf(), e))?
+        .len();
+    registration::check_write(marker, &dir.join(&name), size)?;
+    fs::create_dir_all(&dir).map_err(|e| PathIoError::Io(dir.clone(), e))?;
+    let mut target = dir.join(&name);
+    let mut n = 1;
+    let mut file = loop {
+        match fs::File::create_new(&target) {
+            Ok(file) => break file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                n += 1;
+                target = conflict::numbered(&dir.join(&name), &n.to_string());
+            }
+            Err(e) => return Err(PathIoError::Io(target, e)),
+        }
+    };
+    let result = fs::File::open(source)
+        .and_then(|mut reader| io::copy(&mut reader, &mut file))
+        .and_then(|bytes| file.sync_all().map(|_| bytes));
+    let bytes = match result {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let _ = fs::remove_file(&target);
+            return Err(PathIoError::Io(source.to_path_buf(), e));
+        }
+    };
+    io_stats::record_write::<P>(bytes as usize);
+    let relative = target
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    external::record_owned(&target);
+    written(marker, Path::new(&relative));
+    Ok(relative)
+}
+
+/// Turns an arbitrary file name into one that passes the crate's validation rules.
+///
+/// Separators, braces, control characters and characters invalid on Windows become `_`, trailing
+/// dots and spaces are removed, reserved names like `CON` get a `_` prefix and over-long
+/// names are shortened with [`long_names::shorten`].
+pub fn sanitize_file_name(name: &str) -> String {
+    let replaced: String = normalize_component(name)
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' | '{' | '}' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let mut name = replaced.trim().trim_end_matches(['.', ' ']).to_string();
+    if name.is_empty() {
+        name = "file".to_string();
+    }
+    if name.starts_with('~') || validate_component(&name).is_err() {
+        name.insert(0, '_');
+    }
+    long_names::shorten(&name, long_names::MAX_COMPONENT_BYTES)
+}
+
+/// Lists the files and directories directly inside `sub_path` of `marker`, ordered by
+/// `order`. Use `""` for the marker directory itself. A missing directory is empty.
+pub fn list<P: TypedPath>(
+    marker: &P,
+    sub_path: impl AsRef<Path>,
+    order: sort::SortOrder,
+) -> Result<Vec<PathBuf>, PathIoError> {
+    let sub_path = sub_path.as_ref();
+    let dir = if sub_path.as_os_str().is_empty() {
+        marker.resolve()?
+    } else {
+        resolve_in(marker, sub_path)?
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(PathIoError::Io(dir, e)),
+    };
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| PathIoError::Io(dir, e))?;
+    io_stats::record_read::<P>(0);
+    sort::sort_paths(&mut paths, order);
+    Ok(paths)
+}
+
+/// Bookkeeping after a helper wrote `sub_path` inside `marker`.
+fn written<P: TypedPath>(marker: &P, sub_path: &Path) {
+    sync_state::record_write(marker, sub_path);
+    registration::apply_retention(marker);
+}
+
+/// `sub_path` with `/` separators, as matched by migration patterns and stored by
+/// [`sync_state`](crate::sync_state).
+pub(crate) fn sub_path_key(sub_path: &Path) -> String {
+    sub_path.to_string_lossy().replace('\\', "/")
+}
+
+/// Atomically writes `bytes` to `path`, creating missing parent directories.
+///
+/// The data is written to `<path>.tmp`, synced to disk and renamed over `path`,
+/// so readers either see the old or the new content, never a partial file. The new
+/// content is not reported as an [external modification](crate::external).
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), PathIoError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| PathIoError::Io(parent.to_path_buf(), e))?;
+    }
+    let tmp = tmp_path(path);
+    let result = fs::File::create(&tmp).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    });
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp);
+        return Err(PathIoError::Io(tmp, e));
+    }
+    external::owned_write(&[path], || fs::rename(&tmp, path))
+        .map_err(|e| PathIoError::Io(path.to_path_buf(), e))
+}
+
+pub(crate) fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Recursively copies the directory of `from` into the directory of `to`.
+///
+/// Each file is copied to a temporary file and renamed into place, so an interrupted or
+/// cancelled copy never leaves a partial file under its final name. Symbolic links are
+/// skipped, see [`walk_files`].
+///
+/// Progress is reported through `progress`, which is checked for cancellation after every chunk.
+pub fn copy_marker<A: TypedPath, B: TypedPath>(
+    from: &A,
+    to: &B,
+    progress: &ProgressHandle,
+) -> Result<(), PathIoError> {
+    let span = info_span!(
+        "bevy_paths::io",
+        op = "copy_marker",
+        marker = type_name::<A>(),
+        target = type_name::<B>(),
+        path = field::Empty,
+        target_path = field::Empty
+    )
+    .entered();
+    let (source, target) = (from.resolve()?, to.resolve()?);
+    span.record("path", field::display(RedactedPath(&source)));
+    span.record("target_path", field::display(RedactedPath(&target)));
+    let result = walk_files(&source).and_then(|files| {
+        let mut size = 0;
+        for file in &files {
+            size += fs::metadata(file)
+                .map_err(|e| PathIoError::Io(file.clone(), e))?
+                .len();
+        }
+        registration::check_write(to, &target, size)?;
+        copy_files(&source, &target, &files, progress, |bytes| {
+            io_stats::record_read::<A>(bytes);
+            io_stats::record_write::<B>(bytes);
+        })
+    });
+    progress.finish();
+    if result.is_ok() {
+        registration::apply_retention(to);
+    }
+    result
+}
+
+/// Copies `files` below `source` to the same relative paths below `target`, like
+/// [`copy_marker`]. `copied` is called with the size of every copied file.
+pub(crate) fn copy_files(
+    source: &Path,
+    target: &Path,
+    files: &[PathBuf],
+    progress: &ProgressHandle,
+    mut copied: impl FnMut(usize),
+) -> Result<(), PathIoError> {
+    let mut total = 0;
+    for file in files {
+        total += fs::metadata(file)
+            .map_err(|e| PathIoError::Io(file.clone(), e))?
+            .len();
+    }
+    progress.set_total(total);
+
+    let mut buffer = vec![0u8; 64 * 1024];
+    for file in files {
+        let relative = file.strip_prefix(source).unwrap_or(file);
+        let destination = target.join(relative);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).map_err(|e| PathIoError::Io(parent.to_path_buf(), e))?;
+        }
+        let tmp = tmp_path(&destination);
+        let result = copy_file(file, &tmp, &mut buffer, progress);
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        copied(result?);
+        external::owned_write(&[&destination], || fs::rename(&tmp, &destination))
+            .map_err(|e| PathIoError::Io(destination.clone(), e))?;
+    }
+    Ok(())
+}
+
+/// Copies `source` to `destination` in chunks and syncs it, checking for cancellation.
+///
+/// Returns the number of bytes copied.
+fn copy_file(
+    source: &Path,
+    destination: &Path,
+    buffer: &mut [u8],
+    progress: &ProgressHandle,
+) -> Result<usize, PathIoError> {
+    let mut reader =
+        fs::File::open(source).map_err(|e| PathIoError::Io(source.to_path_buf(), e))?;
+    let destination_error = |e| PathIoError::Io(destination.to_path_buf(), e);
+    let mut writer = fs::File::create(destination).map_err(destination_error)?;
+    let mut copied = 0;
+    loop {
+        if progress.is_cancelled() {
+            return Err(PathIoError::Cancelled);
+        }
+        let n = io::Read::read(&mut reader, buffer)
+            .map_err(|e| PathIoError::Io(source.to_path_buf(), e))?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..n]).map_err(destination_error)?;
+        copied += n;
+        progress.advance(n as u64);
+    }
+    writer.sync_all().map_err(destination_error)?;
+    Ok(copied)
+}
+
+/// Lists all files below `dir` recursively. A missing directory has no files.
+///
+/// Symbolic links are skipped rather than followed, so a link cannot pull files from
+/// outside `dir` into a copy or hash, and link cycles cannot make the walk loop forever.
+pub(crate) fn walk_files(dir: &Path) -> Result<Vec<PathBuf>, PathIoError> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(PathIoError::Io(dir, e)),
+        };
+        for entry in entries {
+            let path = entry.map_err(|e| PathIoError::Io(dir.clone(), e))?.path();
+            let file_type = fs::symlink_metadata(&path)
+                .map_err(|e| PathIoError::Io(path.clone(), e))?
+                .file_type();
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,199 @@
+//! Per-marker counters of the reads and writes done through [`io`](crate::io), journals,
+//! WAL saves, recordings, staged downloads and
+//! [`FileHandlePool::append`](crate::handle_pool::FileHandlePool::append).
+//!
+//! Counting is off by default; enable it for all markers with [`set_io_stats_enabled`] or
+//! for single markers with [`count_io`]. The
+//! [`IoStats`] resource, updated by [`PathsPlugin`](crate::PathsPlugin) every frame, holds the
+//! totals per marker type. With the `diagnostic` feature, [`IoStatsDiagnosticsPlugin`]
+//! additionally reports per-frame counts through `bevy_diagnostic`, which makes systems that
+//! hit the disk every frame easy to spot.
+
+use {
+    crate::TypedPath,
+    bevy_ecs::prelude::*,
+    std::{
+        any::{TypeId, type_name},
+        collections::BTreeMap,
+        sync::{
+            Mutex, RwLock,
+            atomic::{AtomicBool, Ordering},
+        },
+    },
+};
+
+/// Read and write counts of a marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IoCounters {
+    /// The number of reads.
+    pub reads: u64,
+    /// The number of bytes read.
+    pub bytes_read: u64,
+    /// The number of writes.
+    pub writes: u64,
+    /// The number of bytes written.
+    pub bytes_written: u64,
+}
+
+impl IoCounters {
+    fn add(&mut self, other: &Self) {
+        self.reads += other.reads;
+        self.bytes_read += other.bytes_read;
+        self.writes += other.writes;
+        self.bytes_written += other.bytes_written;
+    }
+}
+
+/// Total [`IoCounters`] per marker type since counting was enabled.
+#[derive(Resource, Debug, Default)]
+pub struct IoStats {
+    per_marker: BTreeMap<&'static str, IoCounters>,
+    last_frame: IoCounters,
+}
+
+impl IoStats {
+    /// The counters of `P`.
+    pub fn get<P: TypedPath>(&self) -> IoCounters {
+        self.per_marker
+            .get(type_name::<P>())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The counters of all markers with IO, by type name.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &IoCounters)> {
+        self.per_marker
+            .iter()
+            .map(|(name, counters)| (*name, counters))
+    }
+
+    /// The counters of all markers during the last frame.
+    pub fn last_frame(&self) -> IoCounters {
+        self.last_frame
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PENDING: Mutex<BTreeMap<&'static str, IoCounters>> = Mutex::new(BTreeMap::new());
+
+/// Markers counted while counting is disabled globally.
+static COUNTED: RwLock<Vec<TypeId>> = RwLock::new(Vec::new());
+
+/// Enables or disables counting.
+pub fn set_io_stats_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns `true` if counting is enabled.
+pub fn io_stats_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Counts the reads and writes inside `P`, even while counting is disabled globally.
+pub fn count_io<P: TypedPath>() {
+    let mut counted = COUNTED.write().unwrap_or_else(|e| e.into_inner());
+    if !counted.contains(&TypeId::of::<P>()) {
+        counted.push(TypeId::of::<P>());
+    }
+}
+
+/// Returns `true` if the reads and writes inside `P` are counted.
+pub fn is_io_counted<P: TypedPath>() -> bool {
+    io_stats_enabled()
+        || COUNTED
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&TypeId::of::<P>())
+}
+
+pub(crate) fn record_read<P: TypedPath>(bytes: usize) {
+    record::<P>(IoCounters {
+        reads: 1,
+        bytes_read: bytes as u64,
+        ..Default::default()
+    });
+}
+
+pub(crate) fn record_write<P: TypedPath>(bytes: usize) {
+    record::<P>(IoCounters {
+        writes: 1,
+        bytes_written: bytes as u64,
+        ..Default::default()
+    });
+}
+
+fn record<P: TypedPath>(counters: IoCounters) {
+    if !is_io_counted::<P>() {
+        return;
+    }
+    PENDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(type_name::<P>())
+        .or_default()
+        .add(&counters);
+}
+
+pub(crate) fn collect_io_stats(mut stats: ResMut<IoStats>) {
+    let pending = std::mem::take(&mut *PENDING.lock().unwrap_or_else(|e| e.into_inner()));
+    let mut frame = IoCounters::default();
+    for (marker, counters) in pending {
+        frame.add(&counters);
+        stats.per_marker.entry(marker).or_default().add(&counters);
+    }
+    stats.last_frame = frame;
+}
+
+#[cfg(feature = "diagnostic")]
+pub use diagnostics::IoStatsDiagnosticsPlugin;
+
+#[cfg(feature = "diagnostic")]
+mod diagnostics {
+    use {
+        super::IoStats,
+        bevy_app::{App, Last, Plugin},
+        bevy_diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+        bevy_ecs::prelude::*,
+    };
+
+    /// Reports the reads and writes of every frame as diagnostics.
+    ///
+    /// Requires [`PathsPlugin`](crate::PathsPlugin) and enables counting.
+    #[derive(Default)]
+    pub struct IoStatsDiagnosticsPlugin;
+
+    impl IoStatsDiagnosticsPlugin {
+        /// Reads per frame.
+        pub const READS: DiagnosticPath = DiagnosticPath::const_new("bevy_paths/reads");
+        /// Bytes read per frame.
+        pub const BYTES_READ: DiagnosticPath = DiagnosticPath::const_new("bevy_paths/bytes_read");
+        /// Writes per frame.
+        pub const WRITES: DiagnosticPath = DiagnosticPath::const_new("bevy_paths/writes");
+        /// Bytes written per frame.
+        pub const BYTES_WRITTEN: DiagnosticPath =
+            DiagnosticPath::const_new("bevy_paths/bytes_written");
+    }
+
+    impl Plugin for IoStatsDiagnosticsPlugin {
+        fn build(&self, app: &mut App) {
+            super::set_io_stats_enabled(true);
+            app.register_diagnostic(Diagnostic::new(Self::READS))
+                .register_diagnostic(Diagnostic::new(Self::BYTES_READ))
+                .register_diagnostic(Diagnostic::new(Self::WRITES))
+                .register_diagnostic(Diagnostic::new(Self::BYTES_WRITTEN))
+                .add_systems(Last, report.after(super::collect_io_stats));
+        }
+    }
+
+    fn report(stats: Res<IoStats>, mut diagnostics: Diagnostics) {
+        let frame = stats.last_frame();
+        diagnostics.add_measurement(&IoStatsDiagnosticsPlugin::READS, || frame.reads as f64);
+        diagnostics.add_measurement(&IoStatsDiagnosticsPlugin::BYTES_READ, || {
+            frame.bytes_read as f64
+        });
+        diagnostics.add_measurement(&IoStatsDiagnosticsPlugin::WRITES, || frame.writes as f64);
+        diagnostics.add_measurement(&IoStatsDiagnosticsPlugin::BYTES_WRITTEN, || {
+            frame.bytes_written as f64
+        });
+    }
+}
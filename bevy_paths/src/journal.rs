@@ -0,0 +1,356 @@
+//! Append-only journal files with length-prefixed, checksummed records.
+//!
+//! Each record is stored as a little-endian `u32` length, a 4-byte checksum (the start of its
+//! Blake3 hash) and its bytes. A record at the end of the file that was only partially written
+//! (e.g. after a crash) ends the replay, so everything before it stays readable, and
+//! [`Journal::open`] cuts it off before appending. A damaged record followed by more data is
+//! an error instead, and the file is left alone. Use journals for analytics queues, undo logs
+//! or incremental saves.
+
+use {
+    crate::{
+        TypedPath,
+        display::{RedactedPath, redact},
+        external::AppendGuard,
+        io,
+        io::PathIoError,
+        io_stats, registration,
+    },
+    bevy_log::{error, info_span, trace_span},
+    std::{
+        fs::{self, File, OpenOptions},
+        io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write},
+        marker::PhantomData,
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex, Weak},
+        time::{Duration, Instant},
+    },
+};
+
+/// The default interval between two `fsync` calls of a [`Journal`].
+pub const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The size of the length and checksum in front of every record.
+const RECORD_HEADER: u64 = 8;
+
+/// The open file of a journal, shared with [`sync_due_journals`].
+///
+/// The crate's own bookkeeping files in `.bevy_paths/` use it directly.
+pub(crate) struct JournalFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    sync_interval: Duration,
+    /// When the oldest record that is not synced yet was appended.
+    unsynced_since: Option<Instant>,
+    /// Declared after `writer`, so the final state is recorded after the last flush.
+    _owned: AppendGuard,
+}
+
+impl JournalFile {
+    /// Opens the journal at `path`, truncating a torn record at its end.
+    ///
+    /// Fails with [`PathIoError::Format`] without changing the file if a record in the middle
+    /// is damaged.
+    pub(crate) fn open(path: PathBuf) -> Result<Self, PathIoError> {
+        let _span =
+            info_span!("bevy_paths::journal", op = "open", path = %RedactedPath(&path)).entered();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| PathIoError::Io(parent.to_path_buf(), e))?;
+        }
+        let io_error = |e| PathIoError::Io(path.clone(), e);
+        let owned = AppendGuard::new(&path);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(io_error)?;
+        let mut replay = Replay::new(path.clone(), file.try_clone().map_err(io_error)?)?;
+        for record in replay.by_ref() {
+            record?;
+        }
+        file.set_len(replay.valid_len).map_err(io_error)?;
+        file.seek(SeekFrom::End(0)).map_err(io_error)?;
+        Ok(Self {
+            path,
+            writer: BufWriter::new(file),
+            sync_interval: DEFAULT_SYNC_INTERVAL,
+            unsynced_since: None,
+            _owned: owned,
+        })
+    }
+
+    /// Appends one record without syncing it.
+    pub(crate) fn append(&mut self, record: &[u8]) -> Result<(), PathIoError> {
+        let _span = trace_span!(
+            "bevy_paths::journal",
+            op = "append",
+            path = %RedactedPath(&self.path),
+            bytes = record.len()
+        )
+        .entered();
+        write_record(&mut self.writer, record)
+            .map_err(|e| PathIoError::Io(self.path.clone(), e))?;
+        self.unsynced_since.get_or_insert_with(Instant::now);
+        Ok(())
+    }
+
+    /// Removes all records.
+    pub(crate) fn clear(&mut self) -> Result<(), PathIoError> {
+        let io_error = |e| PathIoError::Io(self.path.clone(), e);
+        self.writer.flush().map_err(io_error)?;
+        let file = self.writer.get_mut();
+        file.set_len(0).map_err(io_error)?;
+        file.seek(SeekFrom::Start(0)).map_err(io_error)?;
+        self.sync()
+    }
+
+    pub(crate) fn sync(&mut self) -> Result<(), PathIoError> {
+        let _span =
+            trace_span!("bevy_paths::journal", op = "sync", path = %RedactedPath(&self.path))
+                .entered();
+        self.writer
+            .flush()
+            .and_then(|_| self.writer.get_ref().sync_data())
+            .map_err(|e| PathIoError::Io(self.path.clone(), e))?;
+        self.unsynced_since = None;
+        Ok(())
+    }
+
+    fn sync_if_due(&mut self) -> Result<(), PathIoError> {
+        match self.unsynced_since {
+            Some(since) if since.elapsed() >= self.sync_interval => self.sync(),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Journals that may have unsynced records.
+static OPEN_JOURNALS: Mutex<Vec<Weak<Mutex<JournalFile>>>> = Mutex::new(Vec::new());
+
+/// An open journal file inside the marker `P`.
+///
+/// Records are buffered and synced to disk once the oldest unsynced record is
+/// [`Journal::with_sync_interval`] old. This happens on the next append or, with
+/// [`PathsPlugin`](crate::PathsPlugin), at the end of the frame, so records do not stay
+/// unsynced when appends stop. Dropping the journal syncs it.
+pub struct Journal<P: TypedPath> {
+    path: PathBuf,
+    file: Arc<Mutex<JournalFile>>,
+    _marker: PhantomData<fn() -> P>,
+}
+
+impl<P: TypedPath> Journal<P> {
+    /// Opens the journal `sub_path` inside `marker`, appending to existing records.
+    ///
+    /// A torn record at the end of the file, left by an interrupted write, is truncated, so
+    /// new records follow the last complete one. A damaged record in the middle of the file
+    /// fails with [`PathIoError::Format`], keeping the records after it on disk.
+    pub fn open(marker: &P, sub_path: impl AsRef<Path>) -> Result<Self, PathIoError> {
+        let path = io::resolve_in(marker, sub_path)?;
+        registration::check_writable::<P>(&path)?;
+        let file = Arc::new(Mutex::new(JournalFile::open(path.clone())?));
+        let mut open = OPEN_JOURNALS.lock().unwrap_or_else(|e| e.into_inner());
+        open.retain(|journal| journal.strong_count() > 0);
+        open.push(Arc::downgrade(&file));
+        Ok(Self {
+            path,
+            file,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Sets how long appended records may stay unsynced. `Duration::ZERO` syncs every record.
+    pub fn with_sync_interval(self, interval: Duration) -> Self {
+        self.file().sync_interval = interval;
+        self
+    }
+
+    /// The resolved path of the journal file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends one record.
+    pub fn append(&mut self, record: &[u8]) -> Result<(), PathIoError> {
+        let mut file = self.file();
+        file.append(record)?;
+        io_stats::record_write::<P>(record.len());
+        file.sync_if_due()
+    }
+
+    /// Flushes buffered records and syncs the file to disk.
+    pub fn sync(&mut self) -> Result<(), PathIoError> {
+        self.file().sync()
+    }
+
+    fn file(&self) -> std::sync::MutexGuard<'_, JournalFile> {
+        self.file.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<P: TypedPath> Drop for Journal<P> {
+    fn drop(&mut self) {
+        let _ = self.sync();
+    }
+}
+
+/// Syncs the journals whose oldest unsynced record is older than their sync interval.
+pub(crate) fn sync_due_journals() {
+    let mut open = OPEN_JOURNALS.lock().unwrap_or_else(|e| e.into_inner());
+    open.retain(|journal| {
+        let Some(file) = journal.upgrade() else {
+            return false;
+        };
+        let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = file.sync_if_due() {
+            error!("Failed to sync journal: {}", redact(&e.to_string()));
+        }
+        true
+    });
+}
+
+/// Writes `record` in the journal format.
+pub(crate) fn write_record(writer: &mut impl Write, record: &[u8]) -> std::io::Result<()> {
+    let len = u32::try_from(record.len())
+        .map_err(|_| std::io::Error::new(ErrorKind::InvalidInput, "Record exceeds 4 GiB."))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&checksum(record))?;
+    writer.write_all(record)
+}
+
+fn checksum(record: &[u8]) -> [u8; 4] {
+    let hash = blake3::hash(record);
+    let mut checksum = [0; 4];
+    checksum.copy_from_slice(&hash.as_bytes()[..4]);
+    checksum
+}
+
+/// Iterates over the records of the journal `sub_path` inside `marker`.
+///
+/// A missing journal has no records.
+pub fn replay<P: TypedPath>(marker: &P, sub_path: impl AsRef<Path>) -> Result<Replay, PathIoError> {
+    let replay = replay_path(&io::resolve_in(marker, sub_path)?)?;
+    replay.record_read::<P>();
+    Ok(replay)
+}
+
+/// Iterates over the records of the journal at an already resolved `path`.
+pub fn replay_path(path: &Path) -> Result<Replay, PathIoError> {
+    match File::open(path) {
+        Ok(file) => Replay::new(path.to_path_buf(), file),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Replay {
+            path: path.to_path_buf(),
+            reader: None,
+            remaining: 0,
+            valid_len: 0,
+        }),
+        Err(e) => Err(PathIoError::Io(path.to_path_buf(), e)),
+    }
+}
+
+/// An iterator over journal records, see [`replay`].
+///
+/// Replay ends at an incomplete or damaged record at the end of the file. A damaged record
+/// followed by more data yields a [`PathIoError::Format`] and ends the replay.
+pub struct Replay {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+    /// Bytes left in the file, so corrupt lengths never cause large allocations.
+    remaining: u64,
+    /// The length of the complete records read so far.
+    valid_len: u64,
+}
+
+impl Replay {
+    fn new(path: PathBuf, file: File) -> Result<Self, PathIoError> {
+        let remaining = file
+            .metadata()
+            .map_err(|e| PathIoError::Io(path.clone(), e))?
+            .len();
+        Ok(Self {
+            path,
+            reader: Some(BufReader::new(file)),
+            remaining,
+            valid_len: 0,
+        })
+    }
+
+    /// Counts the replay as one read of the whole file in [`io_stats`] of `P`.
+    pub(crate) fn record_read<P: TypedPath>(&self) {
+        if self.reader.is_some() {
+            io_stats::record_read::<P>(self.remaining as usize);
+        }
+    }
+}
+
+impl Iterator for Replay {
+    type Item = Result<Vec<u8>, PathIoError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let reader = self.reader.as_mut()?;
+        match read_record(reader, self.remaining) {
+            Ok(ReadRecord::Record(record)) => {
+                let len = RECORD_HEADER + record.len() as u64;
+                self.remaining -= len;
+                self.valid_len += len;
+                Some(Ok(record))
+            }
+            // End of the journal, or a torn record from an interrupted write.
+            Ok(ReadRecord::End) => {
+                self.reader = None;
+                None
+            }
+            Ok(ReadRecord::Damaged) => {
+                self.reader = None;
+                Some(Err(PathIoError::Format(
+                    self.path.clone(),
+                    format!(
+                        "The journal record at byte {} is damaged and followed by more records.",
+                        self.valid_len
+                    ),
+                )))
+            }
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                self.reader = None;
+                None
+            }
+            Err(e) => {
+                self.reader = None;
+                Some(Err(PathIoError::Io(self.path.clone(), e)))
+            }
+        }
+    }
+}
+
+enum ReadRecord {
+    Record(Vec<u8>),
+    /// The rest of the file holds no complete record.
+    End,
+    /// The record fails its checksum, but is not the last one in the file.
+    Damaged,
+}
+
+/// Reads the next record. A record failing its checksum counts as torn if it ends the file.
+fn read_record(reader: &mut impl Read, remaining: u64) -> std::io::Result<ReadRecord> {
+    if remaining < RECORD_HEADER {
+        return Ok(ReadRecord::End);
+    }
+    let mut header = [0u8; RECORD_HEADER as usize];
+    reader.read_exact(&mut header)?;
+    let (len, expected) = header.split_at(4);
+    let len = u32::from_le_bytes(len.try_into().expect("header has 4 length bytes"));
+    if u64::from(len) > remaining - RECORD_HEADER {
+        return Ok(ReadRecord::End);
+    }
+    let mut record = vec![0; len as usize];
+    reader.read_exact(&mut record)?;
+    Ok(if checksum(&record) == expected {
+        ReadRecord::Record(record)
+    } else if u64::from(len) == remaining - RECORD_HEADER {
+        ReadRecord::End
+    } else {
+        ReadRecord::Damaged
+    })
+}
@@ -0,0 +1,167 @@
+//! Golden-layout checks for integration tests.
+//!
+//! Build an app with [`startup_app`], then compare the files below a marker directory with an
+//! expected layout using [`assert_layout`]. This catches accidental layout changes in CI.
+//!
+//! Patterns are relative paths using `/`. Inside a component `*` matches any run of characters
+//! and `?` a single character; a `**` component matches any number of directories.
+//! Only files are compared, empty directories are ignored.
+//!
+//! [`startup_app`] stores all data in a fresh temporary directory, so runs never see files of
+//! earlier runs or of the installed game. The base path is process-wide: keep layout tests in
+//! their own integration test file, so unit tests running in parallel are not redirected.
+//!
+//! ```rust,no_run
+//! use bevy::prelude::*;
+//! use bevy_paths::{layout, prelude::*};
+//!
+//! #[derive(Path, Reflect, Default)]
+//! #[file("tests/layout")]
+//! struct LayoutRoot;
+//!
+//! let _app = layout::startup_app(|app| {
+//!     app.add_plugins(MinimalPlugins);
+//! });
+//! layout::assert_layout(&LayoutRoot, &["config/*.ron", "saves/**/meta.ron"]);
+//! ```
+
+use {
+    crate::{PathIoError, PathsPlugin, TypedPath, io},
+    bevy_app::App,
+    std::{
+        env, fmt, fs,
+        path::Path,
+        process,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// The difference between an expected layout and the files on disk.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LayoutMismatch {
+    /// Patterns that matched no file.
+    pub missing: Vec<String>,
+    /// Files that matched no pattern.
+    pub unexpected: Vec<String>,
+}
+
+impl LayoutMismatch {
+    /// Returns `true` if the layout matched.
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl fmt::Display for LayoutMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for pattern in &self.missing {
+            writeln!(f, "missing:    {pattern}")?;
+        }
+        for file in &self.unexpected {
+            writeln!(f, "unexpected: {file}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates an app with [`PathsPlugin`] storing data in a fresh temporary directory, lets
+/// `configure` add the game's plugins and runs the first update, which executes all startup
+/// systems.
+pub fn startup_app(configure: impl FnOnce(&mut App)) -> App {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let base_path = env::temp_dir().join(format!(
+        "bevy_paths_layout_{}_{}",
+        process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&base_path);
+    let mut app = App::new();
+    app.add_plugins(PathsPlugin::default().with_base_path(base_path));
+    configure(&mut app);
+    app.update();
+    app
+}
+
+/// Compares the files below the directory of `marker` with the `expected` patterns.
+pub fn compare_layout<P: TypedPath>(
+    marker: &P,
+    expected: &[&str],
+) -> Result<LayoutMismatch, PathIoError> {
+    let root = marker.resolve()?;
+    let files: Vec<String> = io::walk_files(&root)?
+        .iter()
+        .map(|file| relative_name(&root, file))
+        .collect();
+    let patterns: Vec<Vec<&str>> = expected
+        .iter()
+        .map(|pattern| pattern.split('/').collect())
+        .collect();
+    let matches = |pattern: &[&str], file: &str| {
+        matches_components(pattern, &file.split('/').collect::<Vec<_>>())
+    };
+    Ok(LayoutMismatch {
+        missing: expected
+            .iter()
+            .zip(&patterns)
+            .filter(|(_, pattern)| !files.iter().any(|file| matches(pattern, file)))
+            .map(|(pattern, _)| pattern.to_string())
+            .collect(),
+        unexpected: files
+            .iter()
+            .filter(|file| !patterns.iter().any(|pattern| matches(pattern, file)))
+            .cloned()
+            .collect(),
+    })
+}
+
+/// Panics with a readable report if the files below `marker` do not match `expected`.
+#[track_caller]
+pub fn assert_layout<P: TypedPath>(marker: &P, expected: &[&str]) {
+    match compare_layout(marker, expected) {
+        Ok(mismatch) if mismatch.is_empty() => {}
+        Ok(mismatch) => panic!("Directory layout does not match:\n{mismatch}"),
+        Err(e) => panic!("Failed to read the directory layout: {e}"),
+    }
+}
+
+fn relative_name(root: &Path, file: &Path) -> String {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns `true` if the relative path `path` matches `pattern`, see the module docs.
+pub(crate) fn matches_pattern(pattern: &str, path: &str) -> bool {
+    matches_components(
+        &pattern.split('/').collect::<Vec<_>>(),
+        &path.split('/').collect::<Vec<_>>(),
+    )
+}
+
+fn matches_components(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (Some((&"**", rest)), _) => {
+            matches_components(rest, path)
+                || (!path.is_empty() && matches_components(pattern, &path[1..]))
+        }
+        (Some((expected, rest)), Some((name, tail))) => {
+            let expected: Vec<char> = expected.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            matches_wildcard(&expected, &name) && matches_components(rest, tail)
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn matches_wildcard(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| matches_wildcard(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && matches_wildcard(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches_wildcard(rest, &name[1..]),
+    }
+}
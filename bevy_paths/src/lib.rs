@@ -14,6 +14,62 @@
 //! - **Type-Safe Templates:** Dynamic paths use struct fields (e.g., `id: u8`) to automatically populate templates.
 //! - **Cross-Platform Safety:** Automatically handles OS-specific separators and implements validation for common naming constraints.
 //! - **Project-Aware:** Keeps your data organized under a unified project root (`<base>/<studio>/<project>`).
+//! - **Long Names:** Over-long generated file names fail clearly or are shortened with a
+//!   hash suffix via [`long_names`].
+//! - **Strict Profile:** Optional screening of user-generated names for invisible, bidi
+//!   control and mixed-script characters, and limits on path depth and length via [`profile`].
+//! - **Consent:** A standard, auditable store for privacy consent flags via [`consent`].
+//! - **Display:** Player-friendly path formatting with `~` and truncation, and a privacy mode
+//!   that redacts home directories and user names from logs via [`display`].
+//! - **Safe IO:** Atomic reads and writes inside managed directories via [`io`], and automatic
+//!   resource persistence with [`PersistResourceExt`]. Rapid saves of the same file are
+//!   coalesced via [`debounce`], and listings come in natural, collated or newest-first
+//!   order via [`sort`]. Resolution and IO are instrumented with
+//!   `tracing` spans carrying the marker and the resolved path, redacted like log lines
+//!   (see [`display::set_privacy_mode`]).
+//! - **Startup Checks:** [`PathsPlugin`] warns when the base path needs elevation, is
+//!   redirected by UAC virtualization or lies inside a OneDrive, Dropbox or iCloud folder.
+//! - **Sandbox-Aware:** Detects Flatpak, Snap, macOS app bundles and App Translocation and
+//!   stores data in a writable location instead of next to the executable.
+//! - **Install Root:** Locates shipped, read-only assets separately from user data with
+//!   [`InstallRoot`], and reads fall back to bundled defaults via [`overlay`].
+//! - **IO Statistics:** Optional per-marker read/write counters via [`io_stats`], also as
+//!   `bevy_diagnostic` measurements (`diagnostic` feature).
+//! - **Progress:** [`IoProgress`] messages and cancellation for long-running copies.
+//! - **Integrity:** Blake3 manifests to detect corrupted or tampered files via [`manifest`].
+//! - **Cloud Sync:** Detects and resolves conflicted copies from Dropbox, Nextcloud and
+//!   Syncthing via [`conflict`].
+//! - **Sync Status:** Tracks files written to opted-in markers since the last cloud upload via
+//!   [`sync_state`].
+//! - **External Changes:** [`FileExternallyModified`] messages when files written by the game
+//!   are changed by another process.
+//! - **Resolution Cache:** Optional per-marker LRU caches for hot dynamic templates via
+//!   [`resolve_cache`].
+//! - **Path Handles:** Interned, `Copy` [`PathId`](path_id::PathId)s for storing file
+//!   references in components.
+//! - **Hot Files:** An LRU [`FileHandlePool`] for files appended to every frame.
+//! - **Disk Usage:** Size, file count and age of marker directories, cached and refreshed in
+//!   the background via [`stat`].
+//! - **Maintenance:** Periodic disk jobs on the IO task pool with [`MaintenanceExt`].
+//! - **Layout Tests:** Assert the on-disk directory tree against glob patterns via [`layout`].
+//! - **Journals:** Crash-resilient append-only record files via [`journal`].
+//! - **Directory Saves:** Multi-file saves committed with an atomic swap via [`dir_save`].
+//! - **WAL Saves:** Incremental deltas with atomic compaction and crash recovery via [`wal`].
+//! - **Recordings:** Append-only replay and event streams with an index via [`recording`].
+//! - **Downloads:** Resumable, verified downloads with atomic finalize via [`download`].
+//! - **Memory Mapping:** Read-only mapped views of large data files (`memmap` feature).
+//! - **Migrations:** Per-marker, per-file-pattern format upgrades applied on load via
+//!   [`migration`].
+//! - **Reveal:** Open a marker directory or select a file in the OS file manager
+//!   (`opener` feature).
+//! - **Crash Recovery:** Interrupted directory saves, WAL compactions, downloads and base path
+//!   moves are finished or undone before [`PathsReady`] via [`recovery`].
+//! - **Relocation:** Move all data to another location, e.g. another drive, via
+//!   [`relocate`].
+//! - **Registration:** All per-marker behavior, including read-only markers, categories,
+//!   retention and quotas, in one [`RegistrationOptions`] via
+//!   [`RegistrationExt::register_with`].
+//! - **Scenes:** Save and load `DynamicScene`s to managed paths (`scene` feature).
 //!
 //! ## Usage
 //!
@@ -37,7 +93,7 @@
 //!
 //! fn main() {
 //!     App::new()
-//!         .add_plugins(DefaultPlugins);
+//!         .add_plugins((DefaultPlugins, PathsPlugin::default()));
 //! }
 //!
 //! fn load_system() {
@@ -48,59 +104,212 @@
 //! }
 //! ```
 
-use {bevy_reflect::Reflect, std::path::PathBuf};
+use {
+    bevy_reflect::Reflect,
+    std::{borrow::Cow, path::PathBuf},
+};
 
 /// In prelude are all necessary exports.
 ///
+/// - [`ExternalModificationExt`]
+/// - [`FileHandlePoolExt`]
+/// - [`MaintenanceExt`]
 /// - [`Path`]
+/// - [`PathIoError`]
+/// - [`PathCategory`]
 /// - [`PathValidationError`]
+/// - [`PathWarning`]
+/// - [`PathsPlugin`]
+/// - [`PersistResourceExt`]
+/// - [`RegistrationExt`]
+/// - [`RegistrationOptions`]
+/// - [`SyncConflictExt`]
 /// - [`TypedPath`]
 pub mod prelude {
-    pub use crate::{PathValidationError, TypedPath};
+    pub use crate::{
+        ExternalModificationExt, FileHandlePoolExt, MaintenanceExt, PathCategory, PathIoError,
+        PathValidationError, PathWarning, PathsPlugin, PersistResourceExt, RegistrationExt,
+        RegistrationOptions, SyncConflictExt, TypedPath,
+    };
     pub use bevy_paths_derive::Path;
 }
 
-pub(crate) use bevy_paths_validation::validate_structural_path;
+pub mod conflict;
+pub mod consent;
+pub mod debounce;
+pub mod dir_save;
+pub mod display;
+pub mod download;
+pub mod external;
+pub mod handle_pool;
+pub mod io;
+pub mod io_stats;
+pub mod journal;
+pub mod layout;
+pub mod long_names;
+pub mod maintenance;
+pub mod manifest;
+pub mod migration;
+pub mod overlay;
+pub mod path_id;
+mod persist;
+pub mod platform;
+mod plugin;
+pub mod probe;
+pub mod profile;
+pub mod progress;
+pub mod recording;
+pub mod recovery;
+pub mod registration;
+pub mod relocate;
+pub mod resolve_cache;
+#[cfg(feature = "opener")]
+pub mod reveal;
+#[cfg(feature = "scene")]
+pub mod scene;
+pub mod sort;
+pub mod stat;
+pub mod sync_state;
+pub mod wal;
+
+pub use {
+    conflict::SyncConflictExt,
+    external::{ExternalModificationExt, FileExternallyModified},
+    handle_pool::{FileHandlePool, FileHandlePoolExt},
+    io::PathIoError,
+    maintenance::{MaintenanceExt, MaintenanceReport},
+    persist::{PERSIST_DEBOUNCE, PersistResourceExt},
+    platform::{Confinement, InstallRoot},
+    plugin::PathsPlugin,
+    probe::PathWarning,
+    progress::{IoProgress, ProgressHandle, ProgressTracker},
+    recovery::PathsReady,
+    registration::{PathCategory, RegistrationExt, RegistrationOptions},
+};
+
+pub(crate) use bevy_paths_validation::{
+    SeparatorPolicy, TemplateSegment, normalize_component, parse_template, validate_component,
+    validate_resolved_path_with,
+};
 pub use {bevy_paths_derive::Path, bevy_paths_validation::PathValidationError};
 
 mod private {
     use super::*;
+    use crate::{
+        display::RedactedPath,
+        platform::{self, Confinement},
+    };
     use bevy_reflect::{PartialReflect, Reflect};
     use std::{
         env, fs,
+        hash::{DefaultHasher, Hash, Hasher},
         path::{Path, PathBuf},
+        sync::{Mutex, RwLock},
     };
 
     pub struct PathResolver;
 
+    /// The base path configured with [`PathsPlugin::with_base_path`].
+    static BASE_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+
     impl PathResolver {
         pub fn resolve(
             data: &dyn Reflect,
             template: &str,
             placeholders: &[&str],
+            policy: SeparatorPolicy,
         ) -> Result<PathBuf, PathValidationError> {
-            let relative_path = Self::resolve_template_reflection(template, data, placeholders);
-            let validated_path = validate_structural_path(&relative_path)?;
+            let span = bevy_log::trace_span!(
+                "bevy_paths::resolve",
+                marker = data.reflect_type_path(),
+                template = %RedactedPath(Path::new(template)),
+                path = bevy_log::tracing::field::Empty
+            )
+            .entered();
+            let relative_path = Self::resolve_template_reflection(template, data, placeholders)?;
+            let validated_path = validate_resolved_path_with(&relative_path, policy)?;
+            profile::check_limits(&validated_path)?;
+            profile::screen(&validated_path)?;
+            let validated_path = long_names::apply(validated_path)?;
             let exe_dir = Self::determine_base_path(None)?;
-            Ok(exe_dir.join(validated_path))
+            let path = exe_dir.join(validated_path);
+            span.record(
+                "path",
+                bevy_log::tracing::field::display(RedactedPath(&path)),
+            );
+            Ok(path)
         }
 
         pub fn resolve_template_reflection(
             template: &str,
             data: &dyn Reflect,
             placeholders: &[&str],
-        ) -> String {
-            let mut result = template.to_string();
-            if let Ok(reflect_struct) = data.reflect_ref().as_struct() {
-                for field_name in placeholders {
-                    if let Some(field_value) = reflect_struct.field(field_name) {
-                        let value_str = Self::convert_reflect_to_string(field_value);
-                        let placeholder = format!("{{{field_name}}}");
-                        result = result.replace(&placeholder, &value_str);
+        ) -> Result<String, PathValidationError> {
+            let reflect_struct = data.reflect_ref().as_struct().ok();
+            let mut result = String::with_capacity(template.len());
+            for segment in parse_template(template)? {
+                let value = match segment {
+                    TemplateSegment::Placeholder(name) if placeholders.contains(&name) => {
+                        reflect_struct.and_then(|s| s.field(name))
+                    }
+                    _ => None,
+                };
+                match (segment, value) {
+                    (_, Some(value)) => result.push_str(&Self::convert_reflect_to_string(value)),
+                    (TemplateSegment::Literal(text), None) => result.push_str(text),
+                    (TemplateSegment::Placeholder(name), None) => {
+                        return Err(PathValidationError::MissingPlaceholder(name.to_string()));
+                    }
+                    (TemplateSegment::Reference(name), None) => {
+                        return Err(PathValidationError::MissingPlaceholder(format!("@{name}")));
                     }
                 }
             }
-            result
+            Ok(result)
+        }
+
+        /// How `\` is handled in paths of a marker with the given `REJECT_BACKSLASHES`.
+        pub fn separator_policy(reject_backslashes: bool) -> SeparatorPolicy {
+            if reject_backslashes {
+                SeparatorPolicy::Reject
+            } else {
+                SeparatorPolicy::Normalize
+            }
+        }
+
+        /// A hash of the values of `placeholders` in `data`, in order.
+        ///
+        /// Values are hashed through reflection where the type supports it, so hashing does
+        /// not allocate for strings and integers.
+        pub fn placeholder_hash(data: &dyn Reflect, placeholders: &[&str]) -> u64 {
+            let reflect_struct = data.reflect_ref().as_struct().ok();
+            let mut hasher = DefaultHasher::new();
+            for field_name in placeholders {
+                match reflect_struct.and_then(|s| s.field(field_name)) {
+                    Some(value) => match value.reflect_hash() {
+                        Some(hash) => hasher.write_u64(hash),
+                        None => Self::convert_reflect_to_string(value).hash(&mut hasher),
+                    },
+                    None => hasher.write_u8(0),
+                }
+            }
+            hasher.finish()
+        }
+
+        /// The values of `placeholders` in `data` as they are substituted, in order.
+        pub fn placeholder_values(
+            data: &dyn Reflect,
+            placeholders: &[&str],
+        ) -> Vec<Option<String>> {
+            let reflect_struct = data.reflect_ref().as_struct().ok();
+            placeholders
+                .iter()
+                .map(|name| {
+                    reflect_struct
+                        .and_then(|s| s.field(name))
+                        .map(Self::convert_reflect_to_string)
+                })
+                .collect()
         }
 
         pub fn convert_reflect_to_string(value: &dyn PartialReflect) -> String {
@@ -151,9 +360,18 @@ mod private {
             format!("{value:?}")
         }
 
+        /// Sets the base path used when [`determine_base_path`](Self::determine_base_path)
+        /// is called without an override.
+        pub fn set_base_path(base_path: Option<PathBuf>) {
+            *BASE_PATH.write().unwrap_or_else(|e| e.into_inner()) = base_path;
+            resolve_cache::invalidate();
+        }
+
         pub fn determine_base_path(
             override_path: Option<&Path>,
         ) -> Result<PathBuf, PathValidationError> {
+            let configured = BASE_PATH.read().unwrap_or_else(|e| e.into_inner()).clone();
+            let override_path = override_path.or(configured.as_deref());
             let exe_dir = env::current_exe()
                 .and_then(|p| {
                     p.parent().map(PathBuf::from).ok_or_else(|| {
@@ -170,10 +388,22 @@ mod private {
                     )
                 })?;
 
+            // Inside a sandbox, an app bundle, a translocated location or a system directory
+            // like `/usr/games` the executable directory is read-only.
+            let default_dir = Confinement::detect()
+                .data_dir()
+                .or_else(|| platform::macos_storage_fallback(&exe_dir))
+                .or_else(|| {
+                    Self::is_protected_location(&exe_dir)
+                        .then(|| platform::user_data_fallback(&exe_dir))
+                        .flatten()
+                })
+                .unwrap_or(exe_dir);
+
             let base_path = match override_path {
                 Some(path) if path.is_absolute() => path.to_path_buf(),
-                Some(path) => exe_dir.join(path),
-                None => exe_dir,
+                Some(path) => default_dir.join(path),
+                None => default_dir,
             };
 
             if !base_path.exists() {
@@ -185,12 +415,91 @@ mod private {
                 .canonicalize()
                 .map_err(|e| PathValidationError::BasePathCanonicalizationFailed(base_path, e))?;
 
+            // The data was moved elsewhere with `relocate::move_project_root`.
+            let canonical_path = match relocate::redirect_target(&canonical_path) {
+                Some(target) => target
+                    .canonicalize()
+                    .map_err(|e| PathValidationError::BasePathCanonicalizationFailed(target, e))?,
+                None => canonical_path,
+            };
+
             if canonical_path.parent().is_none() {
                 return Err(PathValidationError::BasePathIsRoot(canonical_path));
             }
 
+            if Self::is_protected_location(&canonical_path) {
+                return Err(PathValidationError::BasePathInProtectedLocation(
+                    canonical_path,
+                ));
+            }
+
             Ok(canonical_path)
         }
+
+        /// Returns `true` if `path` lies inside a system directory that a game must never write to.
+        pub fn is_protected_location(path: &Path) -> bool {
+            let path = Self::comparable(path);
+            Self::protected_locations()
+                .into_iter()
+                .any(|(protected, only_if_readonly)| {
+                    path.starts_with(Self::comparable(&protected))
+                        && (!only_if_readonly || !Self::is_writable(&protected))
+                })
+        }
+
+        /// System directories as `(path, only_if_readonly)` pairs.
+        fn protected_locations() -> Vec<(PathBuf, bool)> {
+            let mut locations = Vec::new();
+            if cfg!(windows) {
+                for (var, only_if_readonly) in [
+                    ("SystemRoot", false),
+                    ("ProgramFiles", true),
+                    ("ProgramFiles(x86)", true),
+                    ("ProgramW6432", true),
+                ] {
+                    if let Some(dir) = env::var_os(var) {
+                        locations.push((PathBuf::from(dir), only_if_readonly));
+                    }
+                }
+            } else {
+                for dir in [
+                    "/bin", "/boot", "/dev", "/etc", "/lib", "/lib64", "/proc", "/sbin", "/sys",
+                    "/usr",
+                ] {
+                    locations.push((PathBuf::from(dir), false));
+                }
+                if cfg!(target_os = "macos") {
+                    locations.push((PathBuf::from("/System"), false));
+                    locations.push((PathBuf::from("/private/etc"), false));
+                }
+            }
+            locations
+        }
+
+        /// Normalizes a path for prefix comparison (case-insensitive and without `\\?\` on Windows).
+        fn comparable(path: &Path) -> PathBuf {
+            if cfg!(windows) {
+                let s = path.to_string_lossy().to_lowercase();
+                PathBuf::from(s.trim_start_matches(r"\\?\").replace('/', "\\"))
+            } else {
+                path.to_path_buf()
+            }
+        }
+
+        /// Probes whether `dir` is writable. The result is cached, as every resolve checks
+        /// the base path.
+        fn is_writable(dir: &Path) -> bool {
+            static PROBED: Mutex<Vec<(PathBuf, bool)>> = Mutex::new(Vec::new());
+            let mut probed = PROBED.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((_, writable)) = probed.iter().find(|(probed, _)| probed == dir) {
+                return *writable;
+            }
+            let probe = dir.join(".bevy_paths_write_probe");
+            let writable = fs::File::create(&probe).is_ok();
+            let _ = fs::remove_file(&probe);
+            probed.push((dir.to_path_buf(), writable));
+            writable
+        }
     }
 }
 
@@ -202,6 +511,15 @@ pub trait TypedPath: Reflect + 'static + Send + Sync {
     const TEMPLATE: &'static str;
     /// The list of placeholders in the template (e.g. `id` for "levels/{id}.map").
     const PLACEHOLDERS: &'static [&'static str];
+    /// Whether `\` in field values and sub paths is rejected instead of treated as `/`.
+    ///
+    /// Set by `#[file(..., separators = "reject")]`.
+    const REJECT_BACKSLASHES: bool = false;
+    /// The placeholders of [`template`](Self::template), including those of referenced markers.
+    ///
+    /// Equal to [`PLACEHOLDERS`](Self::PLACEHOLDERS) unless the template references other
+    /// markers. The derive computes it at compile time, so a reference cycle fails to compile.
+    const COMPOSED_PLACEHOLDERS: &'static [&'static str] = Self::PLACEHOLDERS;
 
     /// At usage of this function, the placeholders are replaced with the values of the fields.
     /// The function also validates the path structure.
@@ -209,7 +527,24 @@ pub trait TypedPath: Reflect + 'static + Send + Sync {
     /// - If the path is invalid, a [PathValidationError] is returned.
     /// - If the path is valid, the resolved path is returned by a `PathBuf` type.
     fn resolve(&self) -> Result<PathBuf, PathValidationError> {
-        private::PathResolver::resolve(self.as_reflect(), Self::TEMPLATE, Self::PLACEHOLDERS)
+        private::PathResolver::resolve(
+            self.as_reflect(),
+            &Self::template(),
+            &Self::placeholders(),
+            private::PathResolver::separator_policy(Self::REJECT_BACKSLASHES),
+        )
+    }
+
+    /// The template with references to other markers (`{@WorldDir}`) expanded.
+    ///
+    /// Equal to [`TEMPLATE`](Self::TEMPLATE) unless the template references other markers.
+    fn template() -> Cow<'static, str> {
+        Cow::Borrowed(Self::TEMPLATE)
+    }
+
+    /// The placeholders of [`template`](Self::template), including those of referenced markers.
+    fn placeholders() -> Cow<'static, [&'static str]> {
+        Cow::Borrowed(Self::COMPOSED_PLACEHOLDERS)
     }
 }
 
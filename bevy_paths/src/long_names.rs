@@ -0,0 +1,174 @@
+//! Handling of generated file names that exceed the file system limit.
+//!
+//! Placeholder values can produce components longer than most file systems allow
+//! ([`MAX_COMPONENT_BYTES`]). By default resolving such a path fails with
+//! [`PathValidationError::ComponentTooLong`]. With [`LongNamePolicy::Hash`] the tail of the
+//! name is replaced by a short hash instead (`verylongname…-a1b2c3.sav`), and the original
+//! name is recorded in `.bevy_paths/long_names.ron` below the base path for [`original_name`].
+
+use {
+    crate::{PathValidationError, display::redact, io, io::PathIoError, private::PathResolver},
+    bevy_log::warn,
+    std::{
+        collections::BTreeMap,
+        fs,
+        path::{Component, PathBuf},
+        sync::{
+            Mutex,
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+        },
+    },
+};
+
+/// The default component limit in bytes, shared by ext4, NTFS, APFS and most other file systems.
+pub const MAX_COMPONENT_BYTES: usize = 255;
+
+/// The smallest component limit, the length of the `…-` marker and hash that replace the tail
+/// of a shortened name.
+pub const MIN_COMPONENT_BYTES: usize = 10;
+
+/// The location of the name mapping, relative to the base path.
+pub const LONG_NAMES_FILE: &str = ".bevy_paths/long_names.ron";
+
+/// What happens to components longer than the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LongNamePolicy {
+    /// Fail with [`PathValidationError::ComponentTooLong`].
+    #[default]
+    Reject,
+    /// Replace the tail of the name with a short hash and record the original name.
+    Hash,
+}
+
+static HASH_LONG_NAMES: AtomicBool = AtomicBool::new(false);
+static MAX_BYTES: AtomicUsize = AtomicUsize::new(MAX_COMPONENT_BYTES);
+
+/// Shortened name to original name.
+type NameMap = BTreeMap<String, String>;
+
+static NAMES: Mutex<Option<NameMap>> = Mutex::new(None);
+
+/// Sets how components longer than the limit are handled.
+pub fn set_long_name_policy(policy: LongNamePolicy) {
+    HASH_LONG_NAMES.store(policy == LongNamePolicy::Hash, Ordering::Relaxed);
+}
+
+/// Returns the current [`LongNamePolicy`].
+pub fn long_name_policy() -> LongNamePolicy {
+    if HASH_LONG_NAMES.load(Ordering::Relaxed) {
+        LongNamePolicy::Hash
+    } else {
+        LongNamePolicy::Reject
+    }
+}
+
+/// Sets the component limit in bytes. Defaults to [`MAX_COMPONENT_BYTES`].
+///
+/// Limits below [`MIN_COMPONENT_BYTES`] are raised to it.
+pub fn set_max_component_bytes(max_bytes: usize) {
+    MAX_BYTES.store(max_bytes.max(MIN_COMPONENT_BYTES), Ordering::Relaxed);
+}
+
+/// Returns the current component limit in bytes.
+pub fn max_component_bytes() -> usize {
+    MAX_BYTES.load(Ordering::Relaxed)
+}
+
+/// Shortens `name` to at most `max_bytes` by replacing the tail of its stem with `…-` and the
+/// first six hex digits of its blake3 hash. The extension is kept if it fits.
+///
+/// Names within the limit are returned unchanged. Limits below [`MIN_COMPONENT_BYTES`] are
+/// raised to it, so the result always ends with the hash.
+pub fn shorten(name: &str, max_bytes: usize) -> String {
+    let max_bytes = max_bytes.max(MIN_COMPONENT_BYTES);
+    if name.len() <= max_bytes {
+        return name.to_string();
+    }
+    let hash = blake3::hash(name.as_bytes()).to_hex();
+    let suffix = format!("…-{}", &hash[..6]);
+    let extension = name
+        .rfind('.')
+        .filter(|&i| i > 0)
+        .map(|i| &name[i..])
+        .filter(|extension| extension.len() + suffix.len() < max_bytes)
+        .unwrap_or("");
+    let stem = &name[..name.len() - extension.len()];
+    let mut end = max_bytes.saturating_sub(suffix.len() + extension.len());
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{suffix}{extension}", &stem[..end])
+}
+
+/// Returns the original name of a component shortened by [`LongNamePolicy::Hash`].
+pub fn original_name(shortened: &str) -> Result<Option<String>, PathIoError> {
+    let mut guard = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(loaded(&mut guard)?.get(shortened).cloned())
+}
+
+/// Applies the [`LongNamePolicy`] to every component of a validated relative path.
+pub(crate) fn apply(relative: PathBuf) -> Result<PathBuf, PathValidationError> {
+    let max_bytes = max_component_bytes();
+    if relative.as_os_str().len() <= max_bytes {
+        return Ok(relative);
+    }
+    let mut result = PathBuf::new();
+    for component in relative.components() {
+        let Component::Normal(os) = component else {
+            result.push(component);
+            continue;
+        };
+        let name = os.to_string_lossy();
+        if name.len() <= max_bytes {
+            result.push(os);
+            continue;
+        }
+        if long_name_policy() == LongNamePolicy::Reject {
+            return Err(PathValidationError::ComponentTooLong(
+                name.into_owned(),
+                max_bytes,
+            ));
+        }
+        let shortened = shorten(&name, max_bytes);
+        if let Err(e) = record(&shortened, &name) {
+            warn!(
+                "Failed to record the original of shortened name '{shortened}': {}",
+                redact(&e.to_string())
+            );
+        }
+        result.push(shortened);
+    }
+    Ok(result)
+}
+
+fn names_path() -> Result<PathBuf, PathIoError> {
+    Ok(PathResolver::determine_base_path(None)?.join(LONG_NAMES_FILE))
+}
+
+fn loaded(guard: &mut Option<NameMap>) -> Result<&mut NameMap, PathIoError> {
+    if guard.is_none() {
+        let path = names_path()?;
+        let names = match fs::read_to_string(&path) {
+            Ok(text) => {
+                ron::from_str(&text).map_err(|e| PathIoError::Format(path, e.to_string()))?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => NameMap::new(),
+            Err(e) => return Err(PathIoError::Io(path, e)),
+        };
+        *guard = Some(names);
+    }
+    Ok(guard.get_or_insert_default())
+}
+
+fn record(shortened: &str, original: &str) -> Result<(), PathIoError> {
+    let mut guard = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    let names = loaded(&mut guard)?;
+    if names.get(shortened).is_some_and(|known| known == original) {
+        return Ok(());
+    }
+    names.insert(shortened.to_string(), original.to_string());
+    let path = names_path()?;
+    let text = ron::ser::to_string_pretty(names, ron::ser::PrettyConfig::default())
+        .map_err(|e| PathIoError::Format(path.clone(), e.to_string()))?;
+    io::write_atomic(&path, text.as_bytes())
+}
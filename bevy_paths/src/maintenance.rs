@@ -0,0 +1,112 @@
+//! Background maintenance jobs running on the [`IoTaskPool`].
+//!
+//! Heavy disk work (cleanups, backups, size metrics) is registered once with an interval and
+//! then runs off the main schedule. Every finished run publishes a [`MaintenanceReport`].
+
+use {
+    crate::io::PathIoError,
+    bevy_app::{App, Update},
+    bevy_ecs::prelude::*,
+    bevy_tasks::{IoTaskPool, Task, TaskPool, block_on, futures_lite::future},
+    std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+};
+
+/// The result of a single maintenance job run.
+#[derive(Message, Debug)]
+pub struct MaintenanceReport {
+    /// The name the job was registered with.
+    pub job: &'static str,
+    /// A short summary on success, or the error that stopped the job.
+    pub result: Result<String, PathIoError>,
+    /// How long the run took.
+    pub duration: Duration,
+}
+
+type MaintenanceFn = dyn Fn() -> Result<String, PathIoError> + Send + Sync;
+
+/// Extension trait for scheduling maintenance jobs.
+pub trait MaintenanceExt {
+    /// Runs `job` on the IO task pool every `interval`, starting with the first update.
+    ///
+    /// A job never overlaps with itself: the next run is scheduled after the previous one finished.
+    fn add_maintenance_job(
+        &mut self,
+        name: &'static str,
+        interval: Duration,
+        job: impl Fn() -> Result<String, PathIoError> + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl MaintenanceExt for App {
+    fn add_maintenance_job(
+        &mut self,
+        name: &'static str,
+        interval: Duration,
+        job: impl Fn() -> Result<String, PathIoError> + Send + Sync + 'static,
+    ) -> &mut Self {
+        if !self.world().contains_resource::<MaintenanceScheduler>() {
+            self.init_resource::<MaintenanceScheduler>()
+                .add_message::<MaintenanceReport>()
+                .add_systems(Update, run_maintenance_jobs);
+        }
+        self.world_mut()
+            .resource_mut::<MaintenanceScheduler>()
+            .jobs
+            .push(ScheduledJob {
+                name,
+                interval,
+                job: Arc::new(job),
+                next_run: Instant::now(),
+                running: None,
+            });
+        self
+    }
+}
+
+#[derive(Resource, Default)]
+struct MaintenanceScheduler {
+    jobs: Vec<ScheduledJob>,
+}
+
+struct ScheduledJob {
+    name: &'static str,
+    interval: Duration,
+    job: Arc<MaintenanceFn>,
+    next_run: Instant,
+    running: Option<Task<MaintenanceReport>>,
+}
+
+fn run_maintenance_jobs(
+    mut scheduler: ResMut<MaintenanceScheduler>,
+    mut reports: MessageWriter<MaintenanceReport>,
+) {
+    let now = Instant::now();
+    for scheduled in &mut scheduler.jobs {
+        if let Some(task) = &mut scheduled.running {
+            if let Some(report) = block_on(future::poll_once(task)) {
+                scheduled.running = None;
+                scheduled.next_run = now + scheduled.interval;
+                reports.write(report);
+            }
+            continue;
+        }
+        if now < scheduled.next_run {
+            continue;
+        }
+        let name = scheduled.name;
+        let job = scheduled.job.clone();
+        let pool = IoTaskPool::get_or_init(TaskPool::default);
+        scheduled.running = Some(pool.spawn(async move {
+            let start = Instant::now();
+            let result = job();
+            MaintenanceReport {
+                job: name,
+                result,
+                duration: start.elapsed(),
+            }
+        }));
+    }
+}
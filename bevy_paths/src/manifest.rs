@@ -0,0 +1,187 @@
+//! Integrity manifests for marker directories.
+//!
+//! A manifest maps every file below a marker to its size and Blake3 hash. It is stored as
+//! [`MANIFEST_FILE`] inside the marker and excluded from itself. Hashing runs in parallel
+//! on the [`ComputeTaskPool`].
+//!
+//! For anti-tamper checks, a manifest can be signed with a [`ManifestSigner`] (usually at
+//! build time with the studio's private key). The signature is stored in [`SIGNATURE_FILE`]
+//! and checked with a [`ManifestVerifier`] before the manifest is trusted.
+
+use {
+    crate::{TypedPath, display::RedactedPath, download::hash_file, io, io::PathIoError},
+    bevy_log::info_span,
+    bevy_tasks::{ComputeTaskPool, TaskPool},
+    serde::{Deserialize, Serialize},
+    std::{any::type_name, collections::BTreeMap, fs, path::Path},
+};
+
+/// The file name of the manifest inside the marker.
+pub const MANIFEST_FILE: &str = "manifest.ron";
+
+/// The file name of the manifest signature inside the marker.
+pub const SIGNATURE_FILE: &str = "manifest.sig";
+
+/// Signs the serialized bytes of a manifest.
+pub trait ManifestSigner {
+    /// Returns the signature for `manifest`.
+    fn sign(&self, manifest: &[u8]) -> Vec<u8>;
+}
+
+/// Verifies a manifest signature created by a [`ManifestSigner`].
+pub trait ManifestVerifier {
+    /// Returns `true` if `signature` is valid for `manifest`.
+    fn verify(&self, manifest: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Size and hash of a single file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// The file size in bytes.
+    pub size: u64,
+    /// The Blake3 hash as a hex string.
+    pub blake3: String,
+}
+
+/// All files of a marker, keyed by their `/`-separated path relative to the marker.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The manifest entries.
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+/// The differences between a manifest and the files on disk.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestReport {
+    /// Files on disk that are not in the manifest.
+    pub added: Vec<String>,
+    /// Files in the manifest that are missing on disk.
+    pub removed: Vec<String>,
+    /// Files whose size or hash changed.
+    pub modified: Vec<String>,
+}
+
+impl ManifestReport {
+    /// Returns `true` if the files match the manifest exactly.
+    pub fn is_clean(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl Manifest {
+    /// Hashes every file below `marker`.
+    pub fn scan<P: TypedPath>(marker: &P) -> Result<Self, PathIoError> {
+        let root = marker.resolve()?;
+        let _span = info_span!(
+            "bevy_paths::manifest_scan",
+            marker = type_name::<P>(),
+            path = %RedactedPath(&root)
+        )
+        .entered();
+        let files: Vec<_> = io::walk_files(&root)?
+            .into_iter()
+            .filter_map(|path| {
+                let key = manifest_key(&root, &path)?;
+                (key != MANIFEST_FILE && key != SIGNATURE_FILE).then_some((key, path))
+            })
+            .collect();
+
+        let pool = ComputeTaskPool::get_or_init(TaskPool::default);
+        let results = pool.scope(|scope| {
+            for (key, path) in files {
+                scope.spawn(async move {
+                    let entry = fs::metadata(&path).and_then(|meta| {
+                        Ok(ManifestEntry {
+                            size: meta.len(),
+                            blake3: hash_file(&path)?,
+                        })
+                    });
+                    (key, entry.map_err(|e| PathIoError::Io(path, e)))
+                });
+            }
+        });
+
+        let mut manifest = Self::default();
+        for (key, entry) in results {
+            manifest.files.insert(key, entry?);
+        }
+        Ok(manifest)
+    }
+
+    /// Compares the manifest against a fresh scan.
+    pub fn diff(&self, current: &Self) -> ManifestReport {
+        let mut report = ManifestReport::default();
+        for (key, entry) in &current.files {
+            match self.files.get(key) {
+                None => report.added.push(key.clone()),
+                Some(expected) if expected != entry => report.modified.push(key.clone()),
+                Some(_) => {}
+            }
+        }
+        report.removed = self
+            .files
+            .keys()
+            .filter(|key| !current.files.contains_key(*key))
+            .cloned()
+            .collect();
+        report
+    }
+}
+
+/// Scans `marker` and writes its manifest to [`MANIFEST_FILE`].
+pub fn build_manifest<P: TypedPath>(marker: &P) -> Result<Manifest, PathIoError> {
+    let manifest = Manifest::scan(marker)?;
+    io::save_ron(marker, MANIFEST_FILE, &manifest)?;
+    Ok(manifest)
+}
+
+/// Compares the files below `marker` against its stored manifest.
+pub fn verify_manifest<P: TypedPath>(marker: &P) -> Result<ManifestReport, PathIoError> {
+    let expected: Manifest = io::load_ron(marker, MANIFEST_FILE)?;
+    Ok(expected.diff(&Manifest::scan(marker)?))
+}
+
+/// Builds the manifest like [`build_manifest`] and signs it with `signer`.
+pub fn build_signed_manifest<P: TypedPath>(
+    marker: &P,
+    signer: &impl ManifestSigner,
+) -> Result<Manifest, PathIoError> {
+    let manifest = build_manifest(marker)?;
+    let bytes = io::read(marker, MANIFEST_FILE)?;
+    io::write(marker, SIGNATURE_FILE, &signer.sign(&bytes))?;
+    Ok(manifest)
+}
+
+/// Checks the manifest signature with `verifier`, then verifies the files like [`verify_manifest`].
+///
+/// Returns [`PathIoError::VerificationFailed`] if the signature is missing or invalid.
+pub fn verify_signed_manifest<P: TypedPath>(
+    marker: &P,
+    verifier: &impl ManifestVerifier,
+) -> Result<ManifestReport, PathIoError> {
+    let bytes = io::read(marker, MANIFEST_FILE)?;
+    let signature = match io::read(marker, SIGNATURE_FILE) {
+        Err(e) if e.is_not_found() => Vec::new(),
+        result => result?,
+    };
+    let path = io::resolve_in(marker, MANIFEST_FILE)?;
+    if !verifier.verify(&bytes, &signature) {
+        return Err(PathIoError::VerificationFailed(
+            path,
+            "invalid manifest signature".to_string(),
+        ));
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let expected: Manifest =
+        ron::from_str(&text).map_err(|e| PathIoError::Format(path, e.to_string()))?;
+    Ok(expected.diff(&Manifest::scan(marker)?))
+}
+
+fn manifest_key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    Some(parts.join("/"))
+}
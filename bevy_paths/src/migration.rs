@@ -0,0 +1,144 @@
+//! Per-marker format migrations applied by the load helpers.
+//!
+//! Register a migration for every format version a group of files went through. Each
+//! migration is scoped to a marker and a file pattern (`*.ron`, `profiles/*/settings.ron`,
+//! see [`layout`](crate::layout) for the syntax), so files with other formats in the same
+//! marker, like a recording index, are left alone. When [`io::load_ron`](crate::io::load_ron)
+//! loads a matching file, it reads the top-level [`VERSION_FIELD`] (missing means `0`) and
+//! applies the migrations in order before deserializing.
+//!
+//! A migration converts the type of one version into the type of the next, so the old format
+//! is described by a plain serde type, enums included. The file on disk keeps its old version
+//! until it is saved again.
+//!
+//! ```rust
+//! # use bevy_paths::{migration, prelude::*};
+//! # use bevy_reflect::Reflect;
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Path, Reflect, Default)]
+//! #[file("config")]
+//! struct ConfigDir;
+//!
+//! #[derive(Deserialize)]
+//! struct SettingsV1 {
+//!     volume: f32,
+//! }
+//!
+//! // v2 added the `fullscreen` flag.
+//! #[derive(Serialize, Deserialize)]
+//! struct SettingsV2 {
+//!     version: u32,
+//!     volume: f32,
+//!     fullscreen: bool,
+//! }
+//!
+//! migration::register_migration::<ConfigDir, _, _>("settings.ron", 1, |old: SettingsV1| {
+//!     Ok(SettingsV2 {
+//!         version: 2,
+//!         volume: old.volume,
+//!         fullscreen: false,
+//!     })
+//! });
+//! ```
+
+use {
+    crate::{TypedPath, io, io::PathIoError, layout},
+    serde::{Deserialize, Serialize, de::DeserializeOwned},
+    std::{
+        any::TypeId,
+        borrow::Cow,
+        collections::{BTreeMap, HashMap},
+        path::Path,
+        sync::{Arc, RwLock},
+    },
+};
+
+/// The top-level field holding the format version.
+pub const VERSION_FIELD: &str = "version";
+
+/// A migration from one RON text to the next, with the types erased.
+type MigrationFn = dyn Fn(&str) -> Result<String, String> + Send + Sync;
+
+/// A migration of the files matching `pattern` from `from_version`.
+struct Migration {
+    pattern: String,
+    from_version: u32,
+    migrate: Arc<MigrationFn>,
+}
+
+/// Migrations per marker.
+static MIGRATIONS: RwLock<Option<HashMap<TypeId, Vec<Migration>>>> = RwLock::new(None);
+
+/// Registers `migrate` to upgrade the files inside `P` matching `pattern` from
+/// `from_version`, deserialized as `Old`, to `from_version + 1`, serialized from `New`.
+///
+/// The version is counted by the loader, so `Old` and `New` only need a version field if the
+/// loaded type has one. A later registration for the same pattern and version replaces the
+/// earlier one.
+pub fn register_migration<P, Old, New>(
+    pattern: &str,
+    from_version: u32,
+    migrate: impl Fn(Old) -> Result<New, String> + Send + Sync + 'static,
+) where
+    P: TypedPath,
+    Old: DeserializeOwned + 'static,
+    New: Serialize + 'static,
+{
+    let migrate = move |text: &str| {
+        let old = ron::from_str(text).map_err(|e| e.to_string())?;
+        ron::to_string(&migrate(old)?).map_err(|e| e.to_string())
+    };
+    let mut migrations = MIGRATIONS.write().unwrap_or_else(|e| e.into_inner());
+    let migrations = migrations
+        .get_or_insert_default()
+        .entry(TypeId::of::<P>())
+        .or_default();
+    migrations.retain(|m| m.pattern != pattern || m.from_version != from_version);
+    migrations.push(Migration {
+        pattern: pattern.to_string(),
+        from_version,
+        migrate: Arc::new(migrate),
+    });
+}
+
+/// The version of a file, read without touching its other fields.
+#[derive(Deserialize)]
+struct Versioned {
+    #[serde(default)]
+    version: u32,
+}
+
+/// Deserializes `text` loaded from `path` (`sub_path` inside `P`), applying registered
+/// migrations.
+pub(crate) fn deserialize<P: TypedPath, D: DeserializeOwned>(
+    path: &Path,
+    sub_path: &Path,
+    text: &str,
+) -> Result<D, PathIoError> {
+    let format_error = |e: String| PathIoError::Format(path.to_path_buf(), e);
+    let sub_path = io::sub_path_key(sub_path);
+    let migrations: BTreeMap<u32, Arc<MigrationFn>> = MIGRATIONS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|migrations| migrations.get(&TypeId::of::<P>()))
+        .into_iter()
+        .flatten()
+        .filter(|m| layout::matches_pattern(&m.pattern, &sub_path))
+        .map(|m| (m.from_version, m.migrate.clone()))
+        .collect();
+    let version = ron::from_str::<Versioned>(text).ok().map(|v| v.version);
+    if version.is_none_or(|version| !migrations.contains_key(&version)) {
+        return ron::from_str(text).map_err(|e| format_error(e.to_string()));
+    }
+
+    let mut text = Cow::Borrowed(text);
+    let mut version = version.unwrap_or_default();
+    while let Some(migrate) = migrations.get(&version) {
+        text = Cow::Owned(
+            migrate(&text).map_err(|e| format_error(format!("migration from v{version}: {e}")))?,
+        );
+        version += 1;
+    }
+    ron::from_str(&text).map_err(|e| format_error(e.to_string()))
+}
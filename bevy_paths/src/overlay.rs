@@ -0,0 +1,88 @@
+//! Read-through overlays of bundled defaults.
+//!
+//! Register a read-only source directory for a user-data marker, typically shipped defaults
+//! inside the [`InstallRoot`](crate::InstallRoot). Reads through [`io::read`],
+//! [`io::load_ron`] and `io::mmap` fall back to the source while the user has no copy of
+//! the file. Writes always go to the marker directory, so the first save creates the user's
+//! copy; [`materialize`] copies a default over explicitly, e.g. before editing it in place.
+//!
+//! ```rust,no_run
+//! # use bevy_paths::{InstallRoot, overlay, prelude::*};
+//! # use bevy_reflect::Reflect;
+//! #[derive(Path, Reflect, Default)]
+//! #[file("config")]
+//! struct ConfigDir;
+//!
+//! let install_root = InstallRoot::detect().unwrap();
+//! overlay::register_overlay::<ConfigDir>(install_root.resolve("defaults/config").unwrap());
+//! ```
+
+use {
+    crate::{TypedPath, io, io::PathIoError, private::PathResolver, validate_resolved_path_with},
+    std::{
+        any::TypeId,
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+        sync::RwLock,
+    },
+};
+
+/// Source directories per marker.
+static OVERLAYS: RwLock<Option<HashMap<TypeId, PathBuf>>> = RwLock::new(None);
+
+/// Registers `source` as the read-only fallback directory for files inside `P`.
+pub fn register_overlay<P: TypedPath>(source: impl Into<PathBuf>) {
+    let mut overlays = OVERLAYS.write().unwrap_or_else(|e| e.into_inner());
+    overlays
+        .get_or_insert_default()
+        .insert(TypeId::of::<P>(), source.into());
+}
+
+/// Returns the fallback directory registered for `P`.
+pub fn overlay_source<P: TypedPath>() -> Option<PathBuf> {
+    OVERLAYS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|overlays| overlays.get(&TypeId::of::<P>()).cloned())
+}
+
+/// Copies the bundled default of `sub_path` into `marker` unless the user already has a copy.
+///
+/// Returns the path inside the marker directory, which may not exist if there is no default.
+pub fn materialize<P: TypedPath>(
+    marker: &P,
+    sub_path: impl AsRef<Path>,
+) -> Result<PathBuf, PathIoError> {
+    let sub_path = sub_path.as_ref();
+    let path = io::resolve_in(marker, sub_path)?;
+    if path.exists() {
+        return Ok(path);
+    }
+    if let Some(default) = fallback::<P>(sub_path)? {
+        let bytes = fs::read(&default).map_err(|e| PathIoError::Io(default, e))?;
+        io::write_atomic(&path, &bytes)?;
+    }
+    Ok(path)
+}
+
+/// The path to read `sub_path` from: `path` if the user has a copy, else the bundled default.
+pub(crate) fn read_path<P: TypedPath>(
+    path: PathBuf,
+    sub_path: &Path,
+) -> Result<PathBuf, PathIoError> {
+    if path.exists() {
+        return Ok(path);
+    }
+    Ok(fallback::<P>(sub_path)?.unwrap_or(path))
+}
+
+fn fallback<P: TypedPath>(sub_path: &Path) -> Result<Option<PathBuf>, PathIoError> {
+    let Some(source) = overlay_source::<P>() else {
+        return Ok(None);
+    };
+    let policy = PathResolver::separator_policy(P::REJECT_BACKSLASHES);
+    let default = source.join(validate_resolved_path_with(sub_path, policy)?);
+    Ok(default.is_file().then_some(default))
+}
@@ -0,0 +1,103 @@
+//! Interned path handles for hot ECS data.
+//!
+//! Entities referencing files store a [`PathId`] (4 bytes, `Copy`) instead of a `PathBuf`.
+//! The [`PathInterner`] resource, inserted by [`PathsPlugin`](crate::PathsPlugin), maps ids
+//! back to paths. Interning the same path twice returns the same id.
+//!
+//! ```rust,no_run
+//! use bevy::prelude::*;
+//! use bevy_paths::{path_id::{PathId, PathInterner}, prelude::*};
+//!
+//! #[derive(Path, Reflect, Debug)]
+//! #[file("textures/{name}.png")]
+//! struct Texture {
+//!     name: String,
+//! }
+//!
+//! #[derive(Component)]
+//! struct Skin(PathId);
+//!
+//! fn spawn(mut commands: Commands, mut paths: ResMut<PathInterner>) {
+//!     let id = paths.intern_marker(&Texture { name: "knight".into() }).unwrap();
+//!     commands.spawn(Skin(id));
+//! }
+//!
+//! fn load(skins: Query<&Skin>, paths: Res<PathInterner>) {
+//!     for skin in &skins {
+//!         let path = paths.get(skin.0);
+//!     }
+//! }
+//! ```
+
+use {
+    crate::{PathValidationError, TypedPath},
+    bevy_ecs::resource::Resource,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
+};
+
+/// A handle to a path interned in a [`PathInterner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PathId(u32);
+
+/// Interns resolved paths and hands out [`PathId`]s for them.
+///
+/// Ids are only meaningful for the interner that created them. Paths are never removed,
+/// so ids stay valid for the lifetime of the app.
+#[derive(Resource, Debug, Default)]
+pub struct PathInterner {
+    paths: Vec<Arc<Path>>,
+    ids: HashMap<Arc<Path>, PathId>,
+}
+
+impl PathInterner {
+    /// Returns the id of `path`, interning it if needed.
+    pub fn intern(&mut self, path: impl AsRef<Path>) -> PathId {
+        let path = path.as_ref();
+        if let Some(&id) = self.ids.get(path) {
+            return id;
+        }
+        let id =
+            PathId(u32::try_from(self.paths.len()).expect("More than u32::MAX paths interned"));
+        let path: Arc<Path> = Arc::from(path);
+        self.paths.push(path.clone());
+        self.ids.insert(path, id);
+        id
+    }
+
+    /// Resolves `marker` and interns the resulting path.
+    pub fn intern_marker<P: TypedPath>(
+        &mut self,
+        marker: &P,
+    ) -> Result<PathId, PathValidationError> {
+        Ok(self.intern(marker.resolve()?))
+    }
+
+    /// Returns the id of `path` if it was interned.
+    pub fn lookup(&self, path: impl AsRef<Path>) -> Option<PathId> {
+        self.ids.get(path.as_ref()).copied()
+    }
+
+    /// Returns the path of `id`, or `None` if `id` belongs to another interner.
+    pub fn get(&self, id: PathId) -> Option<&Path> {
+        self.paths.get(id.0 as usize).map(|path| &**path)
+    }
+
+    /// Returns the path of `id` as an owned `PathBuf`.
+    pub fn to_path_buf(&self, id: PathId) -> Option<PathBuf> {
+        self.get(id).map(Path::to_path_buf)
+    }
+
+    /// The number of interned paths.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Returns `true` if no path was interned.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
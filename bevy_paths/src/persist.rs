@@ -0,0 +1,117 @@
+//! Automatic persistence of resources to managed paths.
+
+use {
+    crate::{TypedPath, display::redact, io, recovery},
+    bevy_app::{App, AppExit, Last, PreStartup},
+    bevy_ecs::prelude::*,
+    bevy_log::error,
+    serde::{Serialize, de::DeserializeOwned},
+    std::{
+        marker::PhantomData,
+        time::{Duration, Instant},
+    },
+};
+
+/// Quiet period after the last change before a persisted resource is written to disk.
+pub const PERSIST_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Extension trait for binding a resource to a file inside a [`TypedPath`] marker.
+pub trait PersistResourceExt {
+    /// Loads `R` from `file_name` inside the marker `P` and keeps the file in sync.
+    ///
+    /// - `R` is loaded in [`PreStartup`], so building the app does no filesystem work and
+    ///   the resource is available from [`Startup`](bevy_app::Startup) on.
+    /// - If the file is missing or unreadable, `R::default()` is used.
+    /// - Whenever `R` changes, it is saved again once it has been stable for [`PERSIST_DEBOUNCE`].
+    /// - Pending changes are flushed immediately on [`AppExit`].
+    ///
+    /// ```rust,no_run
+    /// # use bevy::prelude::*;
+    /// # use bevy_paths::prelude::*;
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Path, Reflect, Default)]
+    /// #[file("config")]
+    /// struct ConfigDir;
+    ///
+    /// #[derive(Resource, Serialize, Deserialize, Default)]
+    /// struct GraphicsSettings {
+    ///     vsync: bool,
+    /// }
+    ///
+    /// App::new().persist_resource::<GraphicsSettings, ConfigDir>("graphics.ron");
+    /// ```
+    fn persist_resource<R, P>(&mut self, file_name: &'static str) -> &mut Self
+    where
+        R: Resource + Serialize + DeserializeOwned + Default,
+        P: TypedPath + Default;
+}
+
+impl PersistResourceExt for App {
+    fn persist_resource<R, P>(&mut self, file_name: &'static str) -> &mut Self
+    where
+        R: Resource + Serialize + DeserializeOwned + Default,
+        P: TypedPath + Default,
+    {
+        self.insert_resource(Persisted::<R, P> {
+            file_name,
+            pending_since: None,
+            _marker: PhantomData,
+        })
+        .add_systems(
+            PreStartup,
+            load_persisted_resource::<R, P>.after(recovery::recover_on_startup),
+        )
+        .add_systems(Last, save_persisted_resource::<R, P>)
+    }
+}
+
+#[derive(Resource)]
+struct Persisted<R: Resource, P: TypedPath> {
+    file_name: &'static str,
+    pending_since: Option<Instant>,
+    _marker: PhantomData<fn() -> (R, P)>,
+}
+
+fn load_persisted_resource<R, P>(mut commands: Commands, state: Res<Persisted<R, P>>)
+where
+    R: Resource + DeserializeOwned + Default,
+    P: TypedPath + Default,
+{
+    let resource = match io::load_ron::<P, R>(&P::default(), state.file_name) {
+        Ok(resource) => resource,
+        Err(e) if e.is_not_found() => R::default(),
+        Err(e) => {
+            error!(
+                "Failed to load persisted resource, using default: {}",
+                redact(&e.to_string())
+            );
+            R::default()
+        }
+    };
+    commands.insert_resource(resource);
+}
+
+fn save_persisted_resource<R, P>(
+    resource: Res<R>,
+    mut state: ResMut<Persisted<R, P>>,
+    mut exit: MessageReader<AppExit>,
+) where
+    R: Resource + Serialize,
+    P: TypedPath + Default,
+{
+    let now = Instant::now();
+    if resource.is_changed() && !resource.is_added() {
+        state.pending_since = Some(now);
+    }
+    let exiting = exit.read().count() > 0;
+    let Some(since) = state.pending_since else {
+        return;
+    };
+    if !exiting && now.duration_since(since) < PERSIST_DEBOUNCE {
+        return;
+    }
+    state.pending_since = None;
+    if let Err(e) = io::save_ron(&P::default(), state.file_name, &*resource) {
+        error!("Failed to persist resource: {}", redact(&e.to_string()));
+    }
+}
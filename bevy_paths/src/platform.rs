@@ -0,0 +1,274 @@
+//! Detection of the environment the game runs in.
+
+use {
+    crate::{PathIoError, SeparatorPolicy, display::redact_path, validate_resolved_path_with},
+    bevy_ecs::resource::Resource,
+    bevy_log::info,
+    std::{
+        env,
+        ffi::OsStr,
+        path::{Path, PathBuf},
+        sync::{Once, OnceLock},
+    },
+};
+
+/// The sandbox the game runs in, if any.
+///
+/// Sandboxed games cannot write next to their executable and only see a subset of the
+/// host filesystem. When confined, the default base path moves to the sandbox data directory.
+/// [`PathsPlugin`](crate::PathsPlugin) inserts the detected value as a resource.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confinement {
+    /// Not sandboxed.
+    None,
+    /// Running inside a Flatpak sandbox.
+    Flatpak,
+    /// Running inside a Snap package.
+    Snap,
+}
+
+impl Confinement {
+    /// Detects the sandbox once and caches the result.
+    pub fn detect() -> Self {
+        static CONFINEMENT: OnceLock<Confinement> = OnceLock::new();
+        *CONFINEMENT.get_or_init(|| {
+            if !cfg!(target_os = "linux") {
+                Self::None
+            } else if env::var_os("FLATPAK_ID").is_some() || Path::new("/.flatpak-info").exists() {
+                Self::Flatpak
+            } else if env::var_os("SNAP").is_some() {
+                Self::Snap
+            } else {
+                Self::None
+            }
+        })
+    }
+
+    /// Returns `true` if the game runs inside a sandbox.
+    pub fn is_confined(self) -> bool {
+        self != Self::None
+    }
+
+    /// The writable, sandbox-visible data directory.
+    ///
+    /// - Flatpak: `$XDG_DATA_HOME` (`~/.var/app/<id>/data`)
+    /// - Snap: `$SNAP_USER_DATA`
+    pub fn data_dir(self) -> Option<PathBuf> {
+        let var = match self {
+            Self::None => return None,
+            Self::Flatpak => "XDG_DATA_HOME",
+            Self::Snap => "SNAP_USER_DATA",
+        };
+        env::var_os(var).map(PathBuf::from)
+    }
+}
+
+/// The macOS `Application Support` directory to use instead of the executable directory.
+///
+/// Returns `Some` when running from inside a `.app` bundle or from a translocated
+/// (quarantined, randomized read-only) location, where exe-relative storage would fail.
+pub fn macos_storage_fallback(exe_dir: &Path) -> Option<PathBuf> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    let home = env::var_os("HOME")?;
+    let dir = application_support_dir(exe_dir, Path::new(&home))?;
+    static LOGGED: Once = Once::new();
+    LOGGED.call_once(|| {
+        info!(
+            "Executable runs from an app bundle or translocated location '{}', storing data in '{}'.",
+            redact_path(exe_dir),
+            redact_path(&dir)
+        );
+    });
+    Some(dir)
+}
+
+/// Platform-independent part of [`macos_storage_fallback`].
+pub(crate) fn application_support_dir(exe_dir: &Path, home: &Path) -> Option<PathBuf> {
+    let translocated = exe_dir
+        .components()
+        .any(|c| c.as_os_str() == "AppTranslocation");
+    let bundle = exe_dir
+        .ancestors()
+        .find(|dir| dir.extension().is_some_and(|ext| ext == "app"));
+    let name = match bundle {
+        Some(bundle) => bundle.file_stem()?.to_os_string(),
+        None if translocated => env::current_exe().ok()?.file_stem()?.to_os_string(),
+        None => return None,
+    };
+    Some(home.join("Library/Application Support").join(name))
+}
+
+/// The per-user data directory to use when the executable directory is a protected system
+/// location, e.g. for games installed to `/usr/games` or `C:\Program Files`.
+///
+/// - Linux: `$XDG_DATA_HOME/<exe>` or `~/.local/share/<exe>`
+/// - macOS: `~/Library/Application Support/<exe>`
+/// - Windows: `%LOCALAPPDATA%\<exe>`
+pub fn user_data_fallback(exe_dir: &Path) -> Option<PathBuf> {
+    let name = env::current_exe().ok()?.file_stem()?.to_os_string();
+    let dir = user_data_dir(&name)?;
+    static LOGGED: Once = Once::new();
+    LOGGED.call_once(|| {
+        info!(
+            "Executable runs from the protected location '{}', storing data in '{}'.",
+            redact_path(exe_dir),
+            redact_path(&dir)
+        );
+    });
+    Some(dir)
+}
+
+/// The per-user data directory for the game `name`, without the logging of
+/// [`user_data_fallback`].
+pub(crate) fn user_data_dir(name: &OsStr) -> Option<PathBuf> {
+    let root = if cfg!(windows) {
+        PathBuf::from(env::var_os("LOCALAPPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        Path::new(&env::var_os("HOME")?).join("Library/Application Support")
+    } else {
+        match env::var_os("XDG_DATA_HOME").map(PathBuf::from) {
+            Some(dir) if dir.is_absolute() => dir,
+            _ => Path::new(&env::var_os("HOME")?).join(".local/share"),
+        }
+    };
+    Some(root.join(name))
+}
+
+/// The number of bytes available to the current user on the volume containing `path`.
+pub(crate) fn available_space(path: &Path) -> std::io::Result<u64> {
+    #[cfg(unix)]
+    {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+        // SAFETY: `path` is NUL-terminated and `stat` is written by `statvfs` on success.
+        if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // SAFETY: `statvfs` succeeded.
+        let stat = unsafe { stat.assume_init() };
+        // The field types differ between platforms.
+        #[allow(clippy::unnecessary_cast)]
+        Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        #[link(name = "kernel32")]
+        unsafe extern "system" {
+            fn GetDiskFreeSpaceExW(
+                directory: *const u16,
+                available: *mut u64,
+                total: *mut u64,
+                free: *mut u64,
+            ) -> i32;
+        }
+        let path: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+        let mut available = 0;
+        // SAFETY: `path` is NUL-terminated; the total and free counts are optional.
+        let ok = unsafe {
+            GetDiskFreeSpaceExW(
+                path.as_ptr(),
+                &mut available,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(available)
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = path;
+        Ok(u64::MAX)
+    }
+}
+
+/// The read-only location the game is installed to, holding shipped assets.
+///
+/// This is distinct from the writable base path that [`TypedPath`](crate::TypedPath) markers
+/// resolve into: never write here, and never look for shipped assets in the base path.
+/// [`PathsPlugin`](crate::PathsPlugin) inserts the detected value as a resource.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct InstallRoot {
+    path: PathBuf,
+    source: InstallSource,
+}
+
+/// How an [`InstallRoot`] was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallSource {
+    /// `BEVY_ASSET_ROOT` was set explicitly.
+    AssetRootOverride,
+    /// `CARGO_MANIFEST_DIR` is set, i.e. the game runs through `cargo run` during development.
+    CargoManifest,
+    /// The `Contents/Resources` directory of a macOS `.app` bundle.
+    MacosBundle,
+    /// The directory containing the executable.
+    Executable,
+}
+
+impl InstallRoot {
+    /// Locates the install root, in the same order Bevy's asset reader does:
+    /// `BEVY_ASSET_ROOT`, `CARGO_MANIFEST_DIR`, the macOS bundle `Resources` directory,
+    /// then the executable directory.
+    pub fn detect() -> Result<Self, PathIoError> {
+        for (var, source) in [
+            ("BEVY_ASSET_ROOT", InstallSource::AssetRootOverride),
+            ("CARGO_MANIFEST_DIR", InstallSource::CargoManifest),
+        ] {
+            if let Some(path) = env::var_os(var) {
+                return Ok(Self {
+                    path: PathBuf::from(path),
+                    source,
+                });
+            }
+        }
+        let exe_dir = env::current_exe()
+            .map_err(|e| PathIoError::Io(PathBuf::from("<executable_path>"), e))?
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        Ok(match bundle_resources_dir(&exe_dir) {
+            Some(path) => Self {
+                path,
+                source: InstallSource::MacosBundle,
+            },
+            None => Self {
+                path: exe_dir,
+                source: InstallSource::Executable,
+            },
+        })
+    }
+
+    /// The install root directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// How the install root was found.
+    pub fn source(&self) -> InstallSource {
+        self.source
+    }
+
+    /// Resolves `relative` inside the install root, validated like resolved templates.
+    pub fn resolve(&self, relative: &str) -> Result<PathBuf, PathIoError> {
+        let relative = validate_resolved_path_with(relative, SeparatorPolicy::Normalize)?;
+        Ok(self.path.join(relative))
+    }
+}
+
+/// The `Contents/Resources` directory if `exe_dir` is the `Contents/MacOS` directory of a
+/// `.app` bundle.
+pub(crate) fn bundle_resources_dir(exe_dir: &Path) -> Option<PathBuf> {
+    let contents = exe_dir.parent()?;
+    let bundle = contents.parent()?;
+    (exe_dir.file_name()? == "MacOS"
+        && contents.file_name()? == "Contents"
+        && bundle.extension()? == "app")
+        .then(|| contents.join("Resources"))
+}
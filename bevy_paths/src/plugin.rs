@@ -0,0 +1,161 @@
+//! The Bevy plugin.
+
+use {
+    crate::{
+        TypedPath,
+        consent::{self, ConsentChanged, ConsentDir},
+        debounce,
+        display::redact,
+        io_stats::{self, IoStats},
+        journal,
+        path_id::PathInterner,
+        platform::{Confinement, InstallRoot},
+        private::PathResolver,
+        probe::{self, PathWarning},
+        profile::{self, PathLimits, ValidationProfile},
+        progress::{self, IoProgress, ProgressTracker},
+        recovery::{self, PathsReady},
+        stat::{self, MarkerStatCache},
+    },
+    bevy_app::{App, Last, Plugin, PreStartup, Update},
+    bevy_ecs::prelude::*,
+    bevy_log::{error, info, warn},
+    std::path::PathBuf,
+};
+
+/// Adds startup checks for the base path, the [`PathWarning`] message, the
+/// detected [`Confinement`] and [`InstallRoot`] resources, the [`PathInterner`] and
+/// [`MarkerStatCache`], [`IoStats`], [`IoProgress`] reporting, [`ConsentChanged`] messages,
+/// the writer of [`debounced_save`](debounce::debounced_save)s and the periodic sync of
+/// [`Journal`](journal::Journal)s.
+///
+/// Paths resolve without the plugin, but it surfaces misconfigured locations early.
+///
+/// Building the plugin does no filesystem work, so adding it never blocks on slow network
+/// drives. Detection, base path creation and the [`recovery`] of interrupted operations run
+/// in [`PreStartup`]; failures are reported as [`PathWarning::BasePathUnavailable`].
+/// [`PathsReady`] is written once recovery finished.
+#[derive(Default, Clone, Debug)]
+pub struct PathsPlugin {
+    base_path: Option<PathBuf>,
+    profile: Option<ValidationProfile>,
+    limits: Option<PathLimits>,
+    consent_dir: Option<ConsentDir>,
+}
+
+impl PathsPlugin {
+    /// Stores data below `base_path` instead of the default location next to the executable.
+    ///
+    /// A relative path is joined onto the default location. Markers resolve without access
+    /// to the app, so the override applies to the whole process.
+    pub fn with_base_path(mut self, base_path: impl Into<PathBuf>) -> Self {
+        self.base_path = Some(base_path.into());
+        self
+    }
+
+    /// Validates resolved paths with `profile`. Defaults to [`ValidationProfile::Standard`].
+    ///
+    /// Like the base path, the profile applies to the whole process. Use
+    /// [`profile::with_profile`] to change it for a single code path.
+    pub fn with_validation_profile(mut self, profile: ValidationProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Bounds the depth and length of resolved paths. Paths are unlimited by default.
+    ///
+    /// Applies to the whole process; use [`profile::with_path_limits`] for a single code path.
+    pub fn with_path_limits(mut self, limits: PathLimits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Stores [`consent`] decisions in the directory of `P`, e.g. the game's config marker,
+    /// instead of `.bevy_paths/` below the base path.
+    pub fn with_consent_marker<P: TypedPath + Default>(mut self) -> Self {
+        self.consent_dir = Some(consent::marker_dir::<P>);
+        self
+    }
+}
+
+impl Plugin for PathsPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(base_path) = &self.base_path {
+            PathResolver::set_base_path(Some(base_path.clone()));
+        }
+        if let Some(profile) = self.profile {
+            profile::set_validation_profile(profile);
+        }
+        if let Some(limits) = self.limits {
+            profile::set_path_limits(limits);
+        }
+        if let Some(dir) = self.consent_dir {
+            consent::set_consent_dir(dir);
+        }
+        app.init_resource::<ProgressTracker>()
+            .init_resource::<PathInterner>()
+            .init_resource::<MarkerStatCache>()
+            .init_resource::<IoStats>()
+            .add_message::<PathWarning>()
+            .add_message::<IoProgress>()
+            .add_message::<ConsentChanged>()
+            .add_message::<PathsReady>()
+            .add_systems(
+                PreStartup,
+                (
+                    detect_environment,
+                    probe_base_path,
+                    recovery::recover_on_startup,
+                )
+                    .chain(),
+            )
+            .add_systems(Update, stat::poll_marker_stats)
+            .add_systems(
+                Last,
+                (
+                    progress::publish_progress,
+                    consent::publish_consent_changes,
+                    io_stats::collect_io_stats,
+                    debounce::write_debounced_saves,
+                    journal::sync_due_journals,
+                ),
+            );
+    }
+}
+
+fn detect_environment(mut commands: Commands) {
+    let confinement = Confinement::detect();
+    if confinement.is_confined() {
+        info!(
+            "Running inside a {confinement:?} sandbox, storing data in the sandbox data directory."
+        );
+    }
+    commands.insert_resource(confinement);
+    match InstallRoot::detect() {
+        Ok(install_root) => commands.insert_resource(install_root),
+        Err(e) => error!(
+            "Failed to determine the install root: {}",
+            redact(&e.to_string())
+        ),
+    }
+}
+
+fn probe_base_path(mut warnings: MessageWriter<PathWarning>) {
+    let base_path = match PathResolver::determine_base_path(None) {
+        Ok(path) => path,
+        Err(e) => {
+            let reason = redact(&e.to_string());
+            error!("Failed to determine the base path: {reason}");
+            warnings.write(PathWarning::BasePathUnavailable { reason });
+            return;
+        }
+    };
+    let checks = [
+        probe::probe_write_access(&base_path),
+        probe::probe_cloud_sync(&base_path),
+    ];
+    for warning in checks.into_iter().flatten() {
+        warn!("{warning}");
+        warnings.write(warning);
+    }
+}
@@ -0,0 +1,206 @@
+//! Startup checks for base path locations that behave unexpectedly.
+
+use {
+    crate::display::redact_path,
+    bevy_ecs::message::Message,
+    std::{
+        env, fmt, fs,
+        io::ErrorKind,
+        path::{Component, Path, PathBuf},
+    },
+};
+
+const PROBE_FILE: &str = ".bevy_paths_probe";
+
+/// A warning about the base path, emitted by [`PathsPlugin`](crate::PathsPlugin) at startup.
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PathWarning {
+    /// Writes to the base path are silently redirected by Windows UAC virtualization
+    /// into the user's `VirtualStore`, so files seem to go missing.
+    ///
+    /// # Recovery
+    /// Use a per-user location (e.g. `%LOCALAPPDATA%`) or ship a manifest declaring `asInvoker`.
+    WriteVirtualized {
+        /// The base path the game tried to write to.
+        base_path: PathBuf,
+        /// Where the writes actually end up.
+        redirected_to: PathBuf,
+    },
+
+    /// Writing to the base path requires elevated privileges.
+    ///
+    /// # Recovery
+    /// Use a per-user location instead of the install directory.
+    ElevationRequired {
+        /// The base path that is not writable.
+        base_path: PathBuf,
+    },
+
+    /// The base path could not be determined or created.
+    ///
+    /// # Recovery
+    /// Check permissions and free space of the location, or that the network drive is reachable.
+    BasePathUnavailable {
+        /// The (redacted) error message.
+        reason: String,
+    },
+
+    /// The base path lies inside a folder synced by a cloud client. Sync clients lock files
+    /// while uploading and replace them with online-only placeholders, which breaks saves.
+    ///
+    /// # Recovery
+    /// Use a location outside of the synced folder, or exclude it from syncing.
+    CloudSynced {
+        /// The synced base path.
+        base_path: PathBuf,
+        /// The client syncing it.
+        provider: CloudProvider,
+    },
+}
+
+impl fmt::Display for PathWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WriteVirtualized {
+                base_path,
+                redirected_to,
+            } => write!(
+                f,
+                "Writes to the base path '{}' are redirected by UAC virtualization to '{}'",
+                redact_path(base_path),
+                redact_path(redirected_to)
+            ),
+            Self::ElevationRequired { base_path } => write!(
+                f,
+                "Writing to the base path '{}' requires elevated privileges",
+                redact_path(base_path)
+            ),
+            Self::BasePathUnavailable { reason } => {
+                write!(f, "The base path is unavailable: {reason}")
+            }
+            Self::CloudSynced {
+                base_path,
+                provider,
+            } => write!(
+                f,
+                "The base path '{}' is synced by {provider}, which can lock files or make them online-only",
+                redact_path(base_path)
+            ),
+        }
+    }
+}
+
+/// A cloud sync client detected by [`probe_cloud_sync`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CloudProvider {
+    /// Microsoft OneDrive.
+    OneDrive,
+    /// Dropbox.
+    Dropbox,
+    /// Apple iCloud Drive.
+    ICloud,
+    /// A macOS File Provider (`~/Library/CloudStorage`) or Windows cloud files placeholder
+    /// of an unknown client.
+    Other,
+}
+
+impl fmt::Display for CloudProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::OneDrive => "OneDrive",
+            Self::Dropbox => "Dropbox",
+            Self::ICloud => "iCloud Drive",
+            Self::Other => "a cloud sync client",
+        })
+    }
+}
+
+/// Probes whether writes to `base_path` work as expected.
+///
+/// A small probe file is written and removed again.
+pub fn probe_write_access(base_path: &Path) -> Option<PathWarning> {
+    let probe = base_path.join(PROBE_FILE);
+    if let Err(e) = fs::write(&probe, b"probe") {
+        return (e.kind() == ErrorKind::PermissionDenied).then(|| PathWarning::ElevationRequired {
+            base_path: base_path.to_path_buf(),
+        });
+    }
+    let redirected = virtual_store_path(&probe).filter(|p| p.exists());
+    let _ = fs::remove_file(&probe);
+    redirected.map(|redirected| PathWarning::WriteVirtualized {
+        base_path: base_path.to_path_buf(),
+        redirected_to: redirected
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or(redirected),
+    })
+}
+
+/// Where UAC virtualization redirects writes to `path`, if it applies on this platform.
+fn virtual_store_path(path: &Path) -> Option<PathBuf> {
+    if !cfg!(windows) {
+        return None;
+    }
+    let local = env::var_os("LOCALAPPDATA")?;
+    let rest: PathBuf = path
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect();
+    Some(PathBuf::from(local).join("VirtualStore").join(rest))
+}
+
+/// Checks whether `base_path` lies inside a cloud-synced folder.
+///
+/// Detection uses the OneDrive environment variables, `.dropbox` marker files, the iCloud and
+/// File Provider locations on macOS and cloud placeholder attributes on Windows.
+pub fn probe_cloud_sync(base_path: &Path) -> Option<PathWarning> {
+    cloud_provider(base_path).map(|provider| PathWarning::CloudSynced {
+        base_path: base_path.to_path_buf(),
+        provider,
+    })
+}
+
+pub(crate) fn cloud_provider(path: &Path) -> Option<CloudProvider> {
+    let onedrive = ["OneDrive", "OneDriveConsumer", "OneDriveCommercial"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .any(|root| !root.is_empty() && path.starts_with(root));
+    if onedrive {
+        return Some(CloudProvider::OneDrive);
+    }
+    for dir in path.ancestors() {
+        if dir.join(".dropbox").is_file() || dir.join(".dropbox.cache").is_dir() {
+            return Some(CloudProvider::Dropbox);
+        }
+        let name = dir.file_name().unwrap_or_default();
+        if name == "com~apple~CloudDocs" || name == "Mobile Documents" {
+            return Some(CloudProvider::ICloud);
+        }
+        if name == "CloudStorage" && dir.parent().is_some_and(|p| p.ends_with("Library")) {
+            return Some(CloudProvider::Other);
+        }
+        if is_cloud_placeholder(dir) {
+            return Some(CloudProvider::Other);
+        }
+    }
+    None
+}
+
+/// Whether `dir` carries the Windows cloud files recall attributes.
+#[cfg(windows)]
+fn is_cloud_placeholder(dir: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+    fs::metadata(dir).is_ok_and(|m| {
+        m.file_attributes() & (FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+            != 0
+    })
+}
+
+#[cfg(not(windows))]
+fn is_cloud_placeholder(_dir: &Path) -> bool {
+    false
+}
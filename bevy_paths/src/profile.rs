@@ -0,0 +1,145 @@
+//! Validation profiles.
+//!
+//! The [`Standard`](ValidationProfile::Standard) profile validates paths with the same
+//! structural rules as templates. The [`Strict`](ValidationProfile::Strict) profile
+//! additionally screens every resolved component with [`screen_confusables`], so
+//! user-generated names (mods, save slots, player names) cannot spoof other files.
+//!
+//! [`PathsPlugin::with_validation_profile`](crate::PathsPlugin::with_validation_profile)
+//! chooses the profile; [`with_profile`] applies another one to a single code path, e.g. the
+//! import of mods.
+//!
+//! Independent of the profile, [`PathLimits`] bound the depth and length of resolved relative
+//! paths, including the sub paths passed to the [`io`](crate::io) helpers. They are set with
+//! [`PathsPlugin::with_path_limits`](crate::PathsPlugin::with_path_limits) or, for a single
+//! code path, [`with_path_limits`].
+
+use {
+    crate::{PathValidationError, resolve_cache},
+    bevy_paths_validation::{screen_confusables, validate_limits},
+    std::{
+        cell::Cell,
+        path::{Component, Path},
+        sync::{
+            RwLock,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread::LocalKey,
+    },
+};
+
+pub use bevy_paths_validation::{PathLimit, PathLimits};
+
+/// How thoroughly resolved paths are validated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValidationProfile {
+    /// Structural validation only.
+    #[default]
+    Standard,
+    /// Structural validation and screening for invisible, bidi control and mixed-script
+    /// characters in templates, placeholder values and sub paths.
+    Strict,
+}
+
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+static LIMITS: RwLock<PathLimits> = RwLock::new(PathLimits {
+    max_components: None,
+    max_length: None,
+});
+
+thread_local! {
+    static SCOPED_PROFILE: Cell<Option<ValidationProfile>> = const { Cell::new(None) };
+    static SCOPED_LIMITS: Cell<Option<PathLimits>> = const { Cell::new(None) };
+}
+
+/// Sets the validation profile for all following resolutions.
+pub(crate) fn set_validation_profile(profile: ValidationProfile) {
+    STRICT.store(profile == ValidationProfile::Strict, Ordering::Relaxed);
+    resolve_cache::invalidate();
+}
+
+/// Runs `f` with `profile` instead of the configured one.
+///
+/// Only resolutions on the current thread are affected, so other systems and tests running
+/// in parallel keep their profile.
+pub fn with_profile<R>(profile: ValidationProfile, f: impl FnOnce() -> R) -> R {
+    scoped(&SCOPED_PROFILE, profile, f)
+}
+
+/// Sets `key` to `value` while `f` runs, restoring the previous value even if `f` panics.
+fn scoped<T: Copy + 'static, R>(
+    key: &'static LocalKey<Cell<Option<T>>>,
+    value: T,
+    f: impl FnOnce() -> R,
+) -> R {
+    struct Restore<T: Copy + 'static>(&'static LocalKey<Cell<Option<T>>>, Option<T>);
+    impl<T: Copy + 'static> Drop for Restore<T> {
+        fn drop(&mut self) {
+            self.0.set(self.1);
+        }
+    }
+    let _restore = Restore(key, key.replace(Some(value)));
+    f()
+}
+
+/// Returns the current [`ValidationProfile`].
+pub fn validation_profile() -> ValidationProfile {
+    if let Some(profile) = SCOPED_PROFILE.get() {
+        return profile;
+    }
+    if STRICT.load(Ordering::Relaxed) {
+        ValidationProfile::Strict
+    } else {
+        ValidationProfile::Standard
+    }
+}
+
+/// Sets the limits for all following resolutions. Paths are unlimited by default.
+///
+/// Violations fail with [`PathValidationError::LimitExceeded`] naming the limit that was hit.
+pub(crate) fn set_path_limits(limits: PathLimits) {
+    *LIMITS.write().unwrap_or_else(|e| e.into_inner()) = limits;
+    resolve_cache::invalidate();
+}
+
+/// Runs `f` with `limits` instead of the configured ones, on the current thread only.
+pub fn with_path_limits<R>(limits: PathLimits, f: impl FnOnce() -> R) -> R {
+    scoped(&SCOPED_LIMITS, limits, f)
+}
+
+/// Returns `true` if [`with_profile`] or [`with_path_limits`] is active on the current thread.
+pub(crate) fn is_scoped() -> bool {
+    SCOPED_PROFILE.get().is_some() || SCOPED_LIMITS.get().is_some()
+}
+
+/// Returns the current [`PathLimits`].
+pub fn path_limits() -> PathLimits {
+    if let Some(limits) = SCOPED_LIMITS.get() {
+        return limits;
+    }
+    *LIMITS.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Checks a relative path, from the base path to the file, against the current limits.
+pub(crate) fn check_limits(relative: &Path) -> Result<(), PathValidationError> {
+    validate_limits(relative, &path_limits())
+}
+
+/// Returns `true` if any limit is set, i.e. [`check_limits`] can fail.
+pub(crate) fn has_limits() -> bool {
+    path_limits() != PathLimits::default()
+}
+
+/// Applies the checks of the current profile to a structurally valid relative path.
+pub(crate) fn screen(relative: &Path) -> Result<(), PathValidationError> {
+    if validation_profile() == ValidationProfile::Standard {
+        return Ok(());
+    }
+    for component in relative.components() {
+        if let Component::Normal(os) = component {
+            screen_confusables(&os.to_string_lossy())?;
+        }
+    }
+    Ok(())
+}
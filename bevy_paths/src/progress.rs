@@ -0,0 +1,117 @@
+//! Progress reporting and cancellation for long-running IO operations.
+//!
+//! Start an operation with [`ProgressTracker::start`] and pass the [`ProgressHandle`] to the
+//! operation (usually running on a task pool). [`PathsPlugin`](crate::PathsPlugin) publishes an
+//! [`IoProgress`] message whenever a tracked operation advanced.
+
+use {
+    bevy_ecs::prelude::*,
+    std::sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
+
+/// Progress of a long-running IO operation.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoProgress {
+    /// The id of the operation, see [`ProgressHandle::operation_id`].
+    pub operation_id: u64,
+    /// Bytes processed so far.
+    pub bytes_done: u64,
+    /// Total bytes of the operation, `0` while unknown.
+    pub bytes_total: u64,
+    /// Whether the operation has ended (completed, failed or cancelled).
+    pub finished: bool,
+}
+
+#[derive(Debug, Default)]
+struct ProgressState {
+    bytes_done: AtomicU64,
+    bytes_total: AtomicU64,
+    cancelled: AtomicBool,
+    finished: AtomicBool,
+}
+
+/// A shared handle for reporting progress and requesting cancellation.
+#[derive(Debug, Clone)]
+pub struct ProgressHandle {
+    operation_id: u64,
+    state: Arc<ProgressState>,
+}
+
+impl ProgressHandle {
+    /// The id of the operation, matching [`IoProgress::operation_id`].
+    pub fn operation_id(&self) -> u64 {
+        self.operation_id
+    }
+
+    /// Sets the total number of bytes of the operation.
+    pub fn set_total(&self, bytes: u64) {
+        self.state.bytes_total.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Adds processed bytes.
+    pub fn advance(&self, bytes: u64) {
+        self.state.bytes_done.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Marks the operation as ended.
+    pub fn finish(&self) {
+        self.state.finished.store(true, Ordering::Release);
+    }
+
+    /// Requests cancellation. The operation stops at its next checkpoint.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` if cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Acquire)
+    }
+
+    /// The current progress.
+    pub fn progress(&self) -> IoProgress {
+        IoProgress {
+            operation_id: self.operation_id,
+            bytes_done: self.state.bytes_done.load(Ordering::Relaxed),
+            bytes_total: self.state.bytes_total.load(Ordering::Relaxed),
+            finished: self.state.finished.load(Ordering::Acquire),
+        }
+    }
+}
+
+/// Tracks running operations and hands out [`ProgressHandle`]s.
+#[derive(Resource, Debug, Default)]
+pub struct ProgressTracker {
+    next_id: u64,
+    running: Vec<(ProgressHandle, Option<IoProgress>)>,
+}
+
+impl ProgressTracker {
+    /// Starts tracking a new operation.
+    pub fn start(&mut self) -> ProgressHandle {
+        self.next_id += 1;
+        let handle = ProgressHandle {
+            operation_id: self.next_id,
+            state: Arc::default(),
+        };
+        self.running.push((handle.clone(), None));
+        handle
+    }
+}
+
+pub(crate) fn publish_progress(
+    mut tracker: ResMut<ProgressTracker>,
+    mut messages: MessageWriter<IoProgress>,
+) {
+    tracker.running.retain_mut(|(handle, last)| {
+        let progress = handle.progress();
+        if *last != Some(progress) {
+            messages.write(progress);
+            *last = Some(progress);
+        }
+        !progress.finished
+    });
+}
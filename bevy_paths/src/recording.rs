@@ -0,0 +1,185 @@
+//! Append-only session recordings (replays, event streams) stored inside a marker.
+//!
+//! By convention recordings live in their own marker, e.g.
+//!
+//! ```rust
+//! # use bevy_paths::prelude::*;
+//! # use bevy_reflect::Reflect;
+//! #[derive(Path, Reflect, Default)]
+//! #[file("recordings")]
+//! struct Recordings;
+//! ```
+//!
+//! Each recording is a file of length-prefixed, checksummed frames in the [`journal`]
+//! format. Finalized recordings are listed in an `index.ron` file next to them.
+
+use {
+    crate::{
+        TypedPath, io,
+        io::PathIoError,
+        journal::{self, Journal, Replay},
+        registration,
+    },
+    bevy_log::{info_span, trace_span},
+    serde::{Deserialize, Serialize},
+    std::{
+        any::type_name,
+        fs::{self, File},
+        io::ErrorKind,
+        path::Path,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// The name of the index file inside the recordings marker.
+pub const RECORDING_INDEX: &str = "index.ron";
+
+/// Metadata about a finalized recording, as stored in the index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordingInfo {
+    /// The file name of the recording inside the marker.
+    pub file_name: String,
+    /// Start time in milliseconds since the Unix epoch.
+    pub started_at_ms: u64,
+    /// Number of frames written.
+    pub frames: u64,
+    /// Size of the recording in bytes.
+    pub bytes: u64,
+}
+
+/// An open recording that frames can be appended to.
+///
+/// Frames are streamed to disk as they come, synced like a [`Journal`]. Call
+/// [`Recording::finalize`] to add the recording to the index. A recording that is dropped
+/// without being finalized stays on disk but is not listed.
+pub struct Recording<P: TypedPath> {
+    marker: P,
+    journal: Journal<P>,
+    info: RecordingInfo,
+}
+
+impl<P: TypedPath> Recording<P> {
+    /// Starts a new recording named `<prefix>_<timestamp>.rec` inside `marker`.
+    ///
+    /// If a recording with that name exists, e.g. from a second start within the same
+    /// millisecond, a counter is appended (`<prefix>_<timestamp>_2.rec`).
+    pub fn start(marker: P, prefix: &str) -> Result<Self, PathIoError> {
+        let _span = info_span!(
+            "bevy_paths::recording",
+            op = "start",
+            marker = type_name::<P>()
+        )
+        .entered();
+        registration::check_writable::<P>(&marker.resolve()?)?;
+        let started_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let mut n = 1;
+        let file_name = loop {
+            let file_name = match n {
+                1 => format!("{prefix}_{started_at_ms}.rec"),
+                n => format!("{prefix}_{started_at_ms}_{n}.rec"),
+            };
+            let path = io::resolve_in(&marker, &file_name)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| PathIoError::Io(parent.to_path_buf(), e))?;
+            }
+            match File::create_new(&path) {
+                Ok(_) => break file_name,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => n += 1,
+                Err(e) => return Err(PathIoError::Io(path, e)),
+            }
+        };
+        Ok(Self {
+            journal: Journal::open(&marker, &file_name)?,
+            marker,
+            info: RecordingInfo {
+                file_name,
+                started_at_ms,
+                frames: 0,
+                bytes: 0,
+            },
+        })
+    }
+
+    /// Appends one frame to the recording.
+    pub fn write_frame(&mut self, frame: &[u8]) -> Result<(), PathIoError> {
+        let _span = trace_span!(
+            "bevy_paths::recording",
+            op = "write_frame",
+            marker = type_name::<P>(),
+            bytes = frame.len()
+        )
+        .entered();
+        self.journal.append(frame)?;
+        self.info.frames += 1;
+        self.info.bytes += 8 + frame.len() as u64;
+        Ok(())
+    }
+
+    /// Returns the metadata of the recording so far.
+    pub fn info(&self) -> &RecordingInfo {
+        &self.info
+    }
+
+    /// Syncs the recording to disk and adds it to the index.
+    pub fn finalize(mut self) -> Result<RecordingInfo, PathIoError> {
+        let _span = info_span!(
+            "bevy_paths::recording",
+            op = "finalize",
+            marker = type_name::<P>(),
+            frames = self.info.frames
+        )
+        .entered();
+        self.journal.sync()?;
+        let mut index = list_recordings(&self.marker)?;
+        index.push(self.info.clone());
+        io::save_ron(&self.marker, RECORDING_INDEX, &index)?;
+        Ok(self.info)
+    }
+}
+
+/// Lists all finalized recordings inside `marker`, oldest first.
+pub fn list_recordings<P: TypedPath>(marker: &P) -> Result<Vec<RecordingInfo>, PathIoError> {
+    match io::load_ron(marker, RECORDING_INDEX) {
+        Err(e) if e.is_not_found() => Ok(Vec::new()),
+        result => result,
+    }
+}
+
+/// Streams the frames of the recording `file_name` inside `marker`, one at a time.
+///
+/// Recordings use the [`journal`] record format, so a truncated trailing frame
+/// from an interrupted session is ignored, and corrupt frame lengths are bounded by the
+/// file size.
+pub fn read_frames<P: TypedPath>(
+    marker: &P,
+    file_name: impl AsRef<Path>,
+) -> Result<Replay, PathIoError> {
+    let path = io::resolve_in(marker, file_name)?;
+    if !path.exists() {
+        return Err(PathIoError::Io(path, ErrorKind::NotFound.into()));
+    }
+    let replay = journal::replay_path(&path)?;
+    replay.record_read::<P>();
+    Ok(replay)
+}
+
+/// Deletes the recording `file_name` inside `marker` and removes it from the index.
+pub fn delete_recording<P: TypedPath>(
+    marker: &P,
+    file_name: impl AsRef<Path>,
+) -> Result<(), PathIoError> {
+    let file_name = file_name.as_ref();
+    let path = io::resolve_in(marker, file_name)?;
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            return Err(PathIoError::Io(path, e));
+        }
+        _ => {}
+    }
+    let mut index = list_recordings(marker)?;
+    index.retain(|info| Path::new(&info.file_name) != file_name);
+    io::save_ron(marker, RECORDING_INDEX, &index)
+}
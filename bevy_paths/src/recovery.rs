@@ -0,0 +1,343 @@
+//! Startup recovery from interrupted multi-step operations.
+//!
+//! Directory save commits ([`dir_save`](crate::dir_save)), WAL compactions
+//! ([`wal`](crate::wal)), download finalizes ([`download`](crate::download)) and base path
+//! moves ([`relocate`](crate::relocate)) each take several filesystem steps. Before the first
+//! step, they record an intent in [`INTENT_JOURNAL_FILE`], and mark it done after the last.
+//!
+//! [`PathsPlugin`](crate::PathsPlugin) replays the intents still pending in [`PreStartup`],
+//! before any resource is loaded, and rolls each operation forward or back. [`PathsReady`] is
+//! written afterwards. Without the plugin, call [`recover_interrupted_operations`] directly.
+//!
+//! Recovery only inspects the files the operation touches, so running it for an operation
+//! that actually completed changes nothing.
+//!
+//! [`PreStartup`]: bevy_app::PreStartup
+
+use {
+    crate::{
+        display::{RedactedPath, redact},
+        external,
+        io::PathIoError,
+        journal::{self, JournalFile},
+        private::PathResolver,
+        relocate,
+    },
+    bevy_ecs::prelude::*,
+    bevy_log::{error, info, warn},
+    serde::{Deserialize, Serialize},
+    std::{
+        fs,
+        path::{Path, PathBuf},
+        sync::Mutex,
+    },
+};
+
+/// The location of the intent journal, relative to the base path.
+pub const INTENT_JOURNAL_FILE: &str = ".bevy_paths/intents.journal";
+
+/// Written by [`PathsPlugin`](crate::PathsPlugin) in [`PreStartup`](bevy_app::PreStartup) once
+/// interrupted operations are recovered and the managed paths are safe to use.
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct PathsReady {
+    /// The number of interrupted operations that were recovered.
+    pub recovered: usize,
+}
+
+/// A multi-step operation. Paths below the base path are stored relative to it, so the
+/// journal stays valid when the base path is moved.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Intent {
+    /// [`commit_dir_save`](crate::dir_save::commit_dir_save) swapping `staging` into `current`.
+    DirSwap {
+        current: PathBuf,
+        staging: PathBuf,
+        backup: PathBuf,
+    },
+    /// [`WalSave::compact`](crate::wal::WalSave::compact) writing `base` at `generation`
+    /// before resetting `wal`.
+    WalCompaction {
+        base: PathBuf,
+        wal: PathBuf,
+        generation: u64,
+    },
+    /// [`StagedDownload::finalize`](crate::download::StagedDownload::finalize) moving the
+    /// verified `partial` file to `target`.
+    DownloadFinalize { partial: PathBuf, target: PathBuf },
+    /// [`move_project_root`](relocate::move_project_root) moving `old_base` to `new_base`.
+    Relocation {
+        old_base: PathBuf,
+        new_base: PathBuf,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+enum Record {
+    Begin(Intent),
+    Done(Intent),
+}
+
+struct IntentLog {
+    base: PathBuf,
+    journal: JournalFile,
+    /// Intents left by earlier runs, to be recovered.
+    pending: Vec<Intent>,
+    /// Intents of this process, which are running or failed. Failed ones are recovered on
+    /// the next start, as the operation may still be running when recovery is triggered.
+    in_flight: Vec<Intent>,
+}
+
+static LOG: Mutex<Option<IntentLog>> = Mutex::new(None);
+
+/// Runs `operation`, recording `intent` before it and marking it done after it succeeded.
+///
+/// A failed operation stays pending, so it is recovered on the next start.
+pub(crate) fn guarded<T>(
+    intent: Intent,
+    operation: impl FnOnce() -> Result<T, PathIoError>,
+) -> Result<T, PathIoError> {
+    let intent = intent.relative_to(&PathResolver::determine_base_path(None)?);
+    with_log(|log| {
+        log.append(&Record::Begin(intent.clone()))?;
+        log.in_flight.push(intent.clone());
+        Ok(())
+    })?;
+    let result = operation()?;
+    let done = with_log(|log| {
+        log.append(&Record::Done(intent.clone()))?;
+        // After a base path move, the intent was loaded from the copied journal.
+        for intents in [&mut log.in_flight, &mut log.pending] {
+            if let Some(index) = intents.iter().position(|other| *other == intent) {
+                intents.remove(index);
+                break;
+            }
+        }
+        if log.in_flight.is_empty() && log.pending.is_empty() {
+            log.journal.clear()?;
+        }
+        Ok(())
+    });
+    if let Err(e) = done {
+        warn!(
+            "Failed to mark a completed operation as done: {}",
+            redact(&e.to_string())
+        );
+    }
+    Ok(result)
+}
+
+/// Closes the intent journal, e.g. before the directory holding it is deleted.
+pub(crate) fn close_log() {
+    *LOG.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Rolls every interrupted operation forward or back and returns how many were recovered.
+///
+/// Operations started by this process are left alone. Operations that fail to recover are
+/// logged and stay pending for the next call.
+pub fn recover_interrupted_operations() -> Result<usize, PathIoError> {
+    let base = PathResolver::determine_base_path(None)?;
+    with_log(|log| {
+        let mut recovered = 0;
+        let mut failed = Vec::new();
+        for intent in std::mem::take(&mut log.pending) {
+            match intent.recover(&base) {
+                Ok(()) => recovered += 1,
+                Err(e) => {
+                    error!(
+                        "Failed to recover an interrupted operation: {}",
+                        redact(&e.to_string())
+                    );
+                    failed.push(intent);
+                }
+            }
+        }
+        log.journal.clear()?;
+        let remaining: Vec<_> = failed.iter().chain(&log.in_flight).cloned().collect();
+        for intent in remaining {
+            log.append(&Record::Begin(intent))?;
+        }
+        log.pending = failed;
+        Ok(recovered)
+    })
+}
+
+pub(crate) fn recover_on_startup(mut ready: MessageWriter<PathsReady>) {
+    let recovered = match recover_interrupted_operations() {
+        Ok(recovered) => recovered,
+        Err(e) => {
+            error!(
+                "Failed to recover interrupted operations: {}",
+                redact(&e.to_string())
+            );
+            0
+        }
+    };
+    if recovered > 0 {
+        info!("Recovered {recovered} interrupted operation(s).");
+    }
+    ready.write(PathsReady { recovered });
+}
+
+fn with_log<T>(f: impl FnOnce(&mut IntentLog) -> Result<T, PathIoError>) -> Result<T, PathIoError> {
+    let base = PathResolver::determine_base_path(None)?;
+    let mut guard = LOG.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.as_ref().is_none_or(|log| log.base != base) {
+        *guard = None;
+        *guard = Some(IntentLog::open(base)?);
+    }
+    f(guard.as_mut().expect("the log was just opened"))
+}
+
+impl IntentLog {
+    fn open(base: PathBuf) -> Result<Self, PathIoError> {
+        let path = base.join(INTENT_JOURNAL_FILE);
+        let mut pending = Vec::new();
+        for record in journal::replay_path(&path)? {
+            let record = record?;
+            let record: Record = std::str::from_utf8(&record)
+                .map_err(|e| e.to_string())
+                .and_then(|text| ron::from_str(text).map_err(|e| e.to_string()))
+                .map_err(|e| PathIoError::Format(path.clone(), e))?;
+            match record {
+                Record::Begin(intent) => pending.push(intent),
+                Record::Done(intent) => {
+                    if let Some(index) = pending.iter().position(|pending| *pending == intent) {
+                        pending.remove(index);
+                    }
+                }
+            }
+        }
+        Ok(Self {
+            base,
+            journal: JournalFile::open(path)?,
+            pending,
+            in_flight: Vec::new(),
+        })
+    }
+
+    /// Appends `record` and syncs it, so it is on disk before the first step.
+    fn append(&mut self, record: &Record) -> Result<(), PathIoError> {
+        let text = ron::to_string(record)
+            .map_err(|e| PathIoError::Format(INTENT_JOURNAL_FILE.into(), e.to_string()))?;
+        self.journal.append(text.as_bytes())?;
+        self.journal.sync()
+    }
+}
+
+impl Intent {
+    fn relative_to(self, base: &Path) -> Self {
+        let relative = |path: PathBuf| match path.strip_prefix(base) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => path,
+        };
+        match self {
+            Self::DirSwap {
+                current,
+                staging,
+                backup,
+            } => Self::DirSwap {
+                current: relative(current),
+                staging: relative(staging),
+                backup: relative(backup),
+            },
+            Self::WalCompaction {
+                base,
+                wal,
+                generation,
+            } => Self::WalCompaction {
+                base: relative(base),
+                wal: relative(wal),
+                generation,
+            },
+            Self::DownloadFinalize { partial, target } => Self::DownloadFinalize {
+                partial: relative(partial),
+                target: relative(target),
+            },
+            // The base paths themselves stay absolute.
+            relocation @ Self::Relocation { .. } => relocation,
+        }
+    }
+
+    fn recover(&self, base: &Path) -> Result<(), PathIoError> {
+        match self {
+            Self::DirSwap {
+                current,
+                staging,
+                backup,
+            } => recover_dir_swap(&base.join(current), &base.join(staging), &base.join(backup)),
+            Self::WalCompaction {
+                base: base_file,
+                wal,
+                generation,
+            } => recover_wal_compaction(&base.join(base_file), &base.join(wal), *generation),
+            Self::DownloadFinalize { partial, target } => {
+                recover_download(&base.join(partial), &base.join(target))
+            }
+            Self::Relocation { old_base, new_base } => {
+                relocate::recover_move(old_base, new_base);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The staging directory was complete when the commit started, so the swap is finished.
+fn recover_dir_swap(current: &Path, staging: &Path, backup: &Path) -> Result<(), PathIoError> {
+    if !current.exists() {
+        let source = if staging.is_dir() { staging } else { backup };
+        if source.exists() {
+            info!(
+                "Completing the interrupted save of '{}'.",
+                RedactedPath(current)
+            );
+            external::owned_write(&[source, current], || fs::rename(source, current))
+                .map_err(|e| PathIoError::Io(current.to_path_buf(), e))?;
+        }
+    }
+    if current.exists() && backup.exists() {
+        external::owned_write(&[backup], || fs::remove_dir_all(backup))
+            .map_err(|e| PathIoError::Io(backup.to_path_buf(), e))?;
+    }
+    Ok(())
+}
+
+/// Once the new base is written, the journal holds deltas that are part of it.
+fn recover_wal_compaction(base: &Path, wal: &Path, generation: u64) -> Result<(), PathIoError> {
+    let base_generation = match fs::read(base) {
+        Ok(bytes) => bytes
+            .first_chunk::<8>()
+            .map(|generation| u64::from_le_bytes(*generation)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(PathIoError::Io(base.to_path_buf(), e)),
+    };
+    if base_generation != Some(generation) {
+        return Ok(());
+    }
+    let wal_generation = journal::replay_path(wal)?
+        .next()
+        .transpose()?
+        .and_then(|header| header.try_into().ok().map(u64::from_le_bytes));
+    if wal_generation.is_some_and(|wal_generation| wal_generation != generation) {
+        info!("Discarding the compacted journal '{}'.", RedactedPath(wal));
+        external::owned_write(&[wal], || fs::remove_file(wal))
+            .map_err(|e| PathIoError::Io(wal.to_path_buf(), e))?;
+    }
+    Ok(())
+}
+
+/// The partial file was verified before the intent was recorded, so it is moved into place.
+fn recover_download(partial: &Path, target: &Path) -> Result<(), PathIoError> {
+    if !partial.is_file() {
+        return Ok(());
+    }
+    info!(
+        "Completing the interrupted download of '{}'.",
+        RedactedPath(target)
+    );
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| PathIoError::Io(parent.to_path_buf(), e))?;
+    }
+    external::owned_write(&[partial, target], || fs::rename(partial, target))
+        .map_err(|e| PathIoError::Io(target.to_path_buf(), e))
+}
@@ -0,0 +1,356 @@
+//! Per-marker behavior configured in one place.
+//!
+//! Persistence, conflict and modification detection, migrations, overlays, the resolution
+//! cache and IO counting each have their own function or extension method. Instead of
+//! calling them one by one, collect the behavior of a marker in [`RegistrationOptions`] and
+//! apply it with [`RegistrationExt::register_with`].
+//!
+//! The options also hold the policies that only exist per marker:
+//!
+//! - [`readonly`](RegistrationOptions::readonly) rejects writes with [`PathIoError::ReadOnly`].
+//! - [`quota`](RegistrationOptions::quota) rejects writes that would grow the directory past a
+//!   size with [`PathIoError::QuotaExceeded`].
+//! - [`keep_last`](RegistrationOptions::keep_last) deletes the oldest entries of the directory
+//!   after a write, e.g. for rotating logs or autosaves.
+//! - [`category`](RegistrationOptions::category) records what kind of data the marker holds,
+//!   queried with [`category`] by settings screens and cleanup tools.
+//!
+//! Read-only markers are checked by every writing helper of the crate: the [`io`](crate::io)
+//! write functions, journals, WAL saves, directory saves, staged downloads, recordings and
+//! the [`FileHandlePool`](crate::FileHandlePool). Quotas and retention apply to the
+//! [`io`](crate::io) functions that write whole files (`write`, `save_ron`,
+//! `import_external_file` and `copy_marker`), as appends grow files while they are open.
+//!
+//! ```rust,no_run
+//! # use bevy::prelude::*;
+//! # use bevy_paths::prelude::*;
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Path, Reflect, Default)]
+//! #[file("saves")]
+//! struct SaveDir;
+//!
+//! #[derive(Resource, Serialize, Deserialize, Default)]
+//! struct SaveSlots(Vec<String>);
+//!
+//! App::new().register_with::<SaveDir>(
+//!     RegistrationOptions::new()
+//!         .create_on_startup()
+//!         .readonly(false)
+//!         .category(PathCategory::Data)
+//!         .keep_last(10)
+//!         .persist_resource::<SaveSlots>("slots.ron")
+//!         .detect_sync_conflicts()
+//!         .count_io(),
+//! );
+//! ```
+
+use {
+    crate::{
+        ExternalModificationExt, PersistResourceExt, SyncConflictExt, TypedPath,
+        display::{RedactedPath, redact},
+        io::PathIoError,
+        io_stats, migration, overlay, resolve_cache, stat, sync_state,
+    },
+    bevy_app::{App, PreStartup},
+    bevy_ecs::prelude::*,
+    bevy_log::{error, warn},
+    serde::{Serialize, de::DeserializeOwned},
+    std::{
+        any::{TypeId, type_name},
+        collections::HashMap,
+        fs,
+        marker::PhantomData,
+        path::{Path, PathBuf},
+        sync::RwLock,
+        time::SystemTime,
+    },
+};
+
+/// The kind of data a marker holds, see [`RegistrationOptions::category`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathCategory {
+    /// Settings and key bindings.
+    Config,
+    /// Saves and other player data that must not be lost.
+    Data,
+    /// Data that can be recreated, like shader caches or thumbnails.
+    Cache,
+    /// Logs, crash reports and recordings.
+    Logs,
+}
+
+/// The per-marker policies of a registration.
+#[derive(Default, Clone, Copy)]
+struct MarkerPolicy {
+    readonly: bool,
+    category: Option<PathCategory>,
+    keep_last: Option<usize>,
+    quota: Option<u64>,
+}
+
+/// Policies per marker.
+static POLICIES: RwLock<Option<HashMap<TypeId, MarkerPolicy>>> = RwLock::new(None);
+
+type Step = Box<dyn FnOnce(&mut App)>;
+
+/// The behavior of the marker `P`, applied with [`RegistrationExt::register_with`].
+pub struct RegistrationOptions<P: TypedPath + Default> {
+    steps: Vec<Step>,
+    _marker: PhantomData<fn() -> P>,
+}
+
+impl<P: TypedPath + Default> Default for RegistrationOptions<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: TypedPath + Default> RegistrationOptions<P> {
+    /// Options without any behavior.
+    pub fn new() -> Self {
+        Self {
+            steps: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates the directory of `P` in [`PreStartup`].
+    pub fn create_on_startup(self) -> Self {
+        self.step(|app| {
+            app.add_systems(PreStartup, create_marker_dir::<P>);
+        })
+    }
+
+    /// Rejects writes inside `P` with [`PathIoError::ReadOnly`] if `readonly` is `true`.
+    pub fn readonly(self, readonly: bool) -> Self {
+        self.policy(move |policy| policy.readonly = readonly)
+    }
+
+    /// Records the kind of data `P` holds, returned by [`category`].
+    pub fn category(self, category: PathCategory) -> Self {
+        self.policy(move |policy| policy.category = Some(category))
+    }
+
+    /// Keeps the `count` newest files and directories directly inside `P`, deleting older
+    /// ones after every whole-file write through [`io`](crate::io). Hidden entries are never
+    /// deleted.
+    pub fn keep_last(self, count: usize) -> Self {
+        self.policy(move |policy| policy.keep_last = Some(count.max(1)))
+    }
+
+    /// Rejects writes that would grow the directory of `P` beyond `bytes` with
+    /// [`PathIoError::QuotaExceeded`].
+    pub fn quota(self, bytes: u64) -> Self {
+        self.policy(move |policy| policy.quota = Some(bytes))
+    }
+
+    /// Binds the resource `R` to `file_name` inside `P`, see
+    /// [`PersistResourceExt::persist_resource`].
+    pub fn persist_resource<R>(self, file_name: &'static str) -> Self
+    where
+        R: Resource + Serialize + DeserializeOwned + Default,
+    {
+        self.step(move |app| {
+            app.persist_resource::<R, P>(file_name);
+        })
+    }
+
+    /// Scans `P` for conflicted copies at startup, see
+    /// [`SyncConflictExt::detect_sync_conflicts`].
+    pub fn detect_sync_conflicts(self) -> Self {
+        self.step(|app| {
+            app.detect_sync_conflicts::<P>();
+        })
+    }
+
+    /// Watches `P` for external modifications, see
+    /// [`ExternalModificationExt::detect_external_modifications`].
+    pub fn detect_external_modifications(self) -> Self {
+        self.step(|app| {
+            app.detect_external_modifications::<P>();
+        })
+    }
+
+    /// Records writes inside `P` as modified, see [`sync_state::track_sync_state`].
+    pub fn track_sync_state(self) -> Self {
+        self.step(|_| sync_state::track_sync_state::<P>())
+    }
+
+    /// Registers a file format migration, see [`migration::register_migration`].
+    pub fn migration<Old, New>(
+        self,
+        pattern: &str,
+        from_version: u32,
+        migrate: impl Fn(Old) -> Result<New, String> + Send + Sync + 'static,
+    ) -> Self
+    where
+        Old: DeserializeOwned + 'static,
+        New: Serialize + 'static,
+    {
+        let pattern = pattern.to_string();
+        self.step(move |_| {
+            migration::register_migration::<P, Old, New>(&pattern, from_version, migrate)
+        })
+    }
+
+    /// Reads missing files from `source`, see [`overlay::register_overlay`].
+    pub fn overlay(self, source: impl Into<PathBuf>) -> Self {
+        let source = source.into();
+        self.step(move |_| overlay::register_overlay::<P>(source))
+    }
+
+    /// Caches up to `capacity` resolved paths, see
+    /// [`resolve_cache::enable_resolution_cache`].
+    pub fn resolution_cache(self, capacity: usize) -> Self {
+        self.step(move |_| resolve_cache::enable_resolution_cache::<P>(capacity))
+    }
+
+    /// Counts the reads and writes inside `P`, see [`io_stats::count_io`].
+    pub fn count_io(self) -> Self {
+        self.step(|_| io_stats::count_io::<P>())
+    }
+
+    fn step(mut self, step: impl FnOnce(&mut App) + 'static) -> Self {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    fn policy(self, update: impl FnOnce(&mut MarkerPolicy) + 'static) -> Self {
+        self.step(move |_| {
+            let mut policies = POLICIES.write().unwrap_or_else(|e| e.into_inner());
+            update(
+                policies
+                    .get_or_insert_default()
+                    .entry(TypeId::of::<P>())
+                    .or_default(),
+            );
+        })
+    }
+}
+
+/// Extension trait for configuring a marker with [`RegistrationOptions`].
+pub trait RegistrationExt {
+    /// Applies all `options` to the marker `P`.
+    fn register_with<P: TypedPath + Default>(
+        &mut self,
+        options: RegistrationOptions<P>,
+    ) -> &mut Self;
+}
+
+impl RegistrationExt for App {
+    fn register_with<P: TypedPath + Default>(
+        &mut self,
+        options: RegistrationOptions<P>,
+    ) -> &mut Self {
+        for step in options.steps {
+            step(self);
+        }
+        self
+    }
+}
+
+fn create_marker_dir<P: TypedPath + Default>() {
+    let result = P::default()
+        .resolve()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| fs::create_dir_all(dir).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        error!(
+            "Failed to create the directory of '{}': {}",
+            type_name::<P>(),
+            redact(&e)
+        );
+    }
+}
+
+fn policy<P: TypedPath>() -> MarkerPolicy {
+    POLICIES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|policies| policies.get(&TypeId::of::<P>()).copied())
+        .unwrap_or_default()
+}
+
+/// The category `P` was registered with.
+pub fn category<P: TypedPath>() -> Option<PathCategory> {
+    policy::<P>().category
+}
+
+/// Returns `true` if `P` was registered as read-only.
+pub fn is_readonly<P: TypedPath>() -> bool {
+    policy::<P>().readonly
+}
+
+/// Fails if `P` is read-only, before `path` inside it is opened for writing.
+pub(crate) fn check_writable<P: TypedPath>(path: &Path) -> Result<(), PathIoError> {
+    if is_readonly::<P>() {
+        return Err(PathIoError::ReadOnly(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+/// Fails if `P` is read-only or writing `bytes` to `path`, replacing the file there, would
+/// exceed its quota.
+pub(crate) fn check_write<P: TypedPath>(
+    marker: &P,
+    path: &Path,
+    bytes: u64,
+) -> Result<(), PathIoError> {
+    check_writable::<P>(path)?;
+    let Some(quota) = policy::<P>().quota else {
+        return Ok(());
+    };
+    let replaced = fs::metadata(path).map_or(0, |metadata| metadata.len());
+    let size = stat::stat(marker)?.size.saturating_sub(replaced) + bytes;
+    if size > quota {
+        return Err(PathIoError::QuotaExceeded(path.to_path_buf(), quota));
+    }
+    Ok(())
+}
+
+/// Deletes the entries of `P` beyond its [`keep_last`](RegistrationOptions::keep_last)
+/// count, oldest first. Failures are logged, as the write itself succeeded.
+pub(crate) fn apply_retention<P: TypedPath>(marker: &P) {
+    let Some(keep) = policy::<P>().keep_last else {
+        return;
+    };
+    let dir = match marker.resolve() {
+        Ok(dir) => dir,
+        Err(e) => {
+            warn!("Failed to apply retention: {}", redact(&e.to_string()));
+            return;
+        }
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return;
+    };
+    let mut entries: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| {
+            // Hidden entries and files of atomic writes in progress.
+            let name = entry.file_name().to_string_lossy().into_owned();
+            !name.starts_with('.') && !name.ends_with(".tmp")
+        })
+        .map(|entry| {
+            let modified = entry.metadata().and_then(|m| m.modified());
+            (modified.unwrap_or(SystemTime::UNIX_EPOCH), entry.path())
+        })
+        .collect();
+    // Newest first, ties broken by name, as some filesystems store coarse timestamps.
+    entries.sort_by(|a, b| b.cmp(a));
+    for (_, path) in entries.into_iter().skip(keep) {
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(e) = result {
+            warn!(
+                "Failed to delete '{}' for retention: {}",
+                RedactedPath(&path),
+                redact(&e.to_string())
+            );
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Moving the whole base path to another location, e.g. another drive.
+//!
+//! [`move_project_root`] is the backend for a "change storage location" setting. It copies
+//! everything below the current base path with progress, then leaves a redirect stamp at
+//! the old location ([`REDIRECT_FILE`]). The base path is resolved as before on every start
+//! and the stamp is followed from there, so the move persists without further configuration.
+//! An interrupted move is finished or undone by [`recovery`] on the next start.
+
+use {
+    crate::{
+        display::{RedactedPath, redact},
+        io::{self, PathIoError},
+        platform,
+        private::PathResolver,
+        progress::ProgressHandle,
+        recovery::{self, Intent},
+        resolve_cache, sync_state,
+    },
+    bevy_log::{info_span, warn},
+    serde::{Deserialize, Serialize},
+    std::{
+        env, fs,
+        path::{Component, Path, PathBuf},
+        sync::Mutex,
+    },
+};
+
+/// The location of the redirect stamp, relative to a base path that was moved.
+pub const REDIRECT_FILE: &str = ".bevy_paths/redirect.ron";
+
+/// Redirects followed at most, so a cycle of stamps cannot hang resolution.
+const MAX_REDIRECTS: usize = 8;
+
+#[derive(Serialize, Deserialize)]
+struct Redirect {
+    target: PathBuf,
+}
+
+/// Followed redirects per base path, as the stamp would otherwise be read on every resolution.
+static REDIRECTS: Mutex<Vec<(PathBuf, Option<PathBuf>)>> = Mutex::new(Vec::new());
+
+/// The location `base` was moved to, following chained moves, or `None` if it was not moved.
+pub(crate) fn redirect_target(base: &Path) -> Option<PathBuf> {
+    let mut redirects = REDIRECTS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, target)) = redirects.iter().find(|(path, _)| path == base) {
+        return target.clone();
+    }
+    let mut target = None;
+    for _ in 0..MAX_REDIRECTS {
+        match read_stamp(target.as_deref().unwrap_or(base)) {
+            Some(next) => target = Some(next),
+            None => break,
+        }
+    }
+    redirects.push((base.to_path_buf(), target.clone()));
+    target
+}
+
+/// The target of the redirect stamp at `base`, without following further moves.
+fn read_stamp(base: &Path) -> Option<PathBuf> {
+    let stamp = base.join(REDIRECT_FILE);
+    let text = fs::read_to_string(&stamp).ok()?;
+    match ron::from_str::<Redirect>(&text) {
+        Ok(redirect) => Some(redirect.target),
+        Err(e) => {
+            warn!(
+                "Ignoring the invalid redirect '{}': {}",
+                RedactedPath(&stamp),
+                redact(&e.to_string())
+            );
+            None
+        }
+    }
+}
+
+/// Moves everything below the current base path to `new_base` and returns its canonical path.
+///
+/// `new_base` must be an absolute path without `..` outside of the current base path and must
+/// be empty or missing. It is only created once all checks passed. The move is rejected with [`PathIoError::RelocationRejected`] if the volume of
+/// `new_base` lacks the space for the data, or if the base path holds the executable (a
+/// portable install, which is moved as a whole instead).
+///
+/// 1. All files are copied with progress reported through `progress`. On failure or
+///    cancellation, the copies are removed and the old base path stays in use.
+/// 2. A redirect stamp at the old location switches all markers to `new_base`, in this
+///    process and on every later start.
+/// 3. The old files are deleted. Failures are logged, as the data is already moved.
+///
+/// Files kept open, like [`Journal`](crate::journal::Journal)s or a
+/// [`FileHandlePool`](crate::FileHandlePool), keep pointing to the old location; close them
+/// before the move.
+pub fn move_project_root(
+    new_base: impl AsRef<Path>,
+    progress: &ProgressHandle,
+) -> Result<PathBuf, PathIoError> {
+    let result = move_project_root_inner(new_base.as_ref(), progress);
+    progress.finish();
+    result
+}
+
+fn move_project_root_inner(
+    new_base: &Path,
+    progress: &ProgressHandle,
+) -> Result<PathBuf, PathIoError> {
+    let rejected =
+        |reason: &str| PathIoError::RelocationRejected(new_base.to_path_buf(), reason.into());
+    let old_base = PathResolver::determine_base_path(None)?;
+    let _span = info_span!(
+        "bevy_paths::relocate",
+        op = "move_project_root",
+        path = %RedactedPath(&old_base),
+        target = %RedactedPath(new_base)
+    )
+    .entered();
+    if !new_base.is_absolute() {
+        return Err(rejected("the path is not absolute"));
+    }
+    if new_base
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        return Err(rejected("the path contains '..'"));
+    }
+    // Validate before creating anything, so a rejected move leaves no directory behind.
+    let existing = new_base
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(new_base);
+    let new_base = existing
+        .canonicalize()
+        .map_err(|e| PathIoError::Io(existing.to_path_buf(), e))?
+        .join(new_base.strip_prefix(existing).unwrap_or(Path::new("")));
+    if new_base.starts_with(&old_base) || old_base.starts_with(&new_base) {
+        return Err(rejected("the path overlaps the current base path"));
+    }
+    if PathResolver::is_protected_location(&new_base) {
+        return Err(rejected("the path is a protected system location"));
+    }
+    if new_base.exists() {
+        let mut entries =
+            fs::read_dir(&new_base).map_err(|e| PathIoError::Io(new_base.clone(), e))?;
+        if entries.next().is_some() {
+            return Err(rejected("the directory is not empty"));
+        }
+    }
+    if env::current_exe()
+        .and_then(|exe| exe.canonicalize())
+        .is_ok_and(|exe| exe.starts_with(&old_base))
+    {
+        return Err(rejected("the base path contains the executable"));
+    }
+
+    let files = io::walk_files(&old_base)?;
+    let mut needed = 0;
+    for file in &files {
+        needed += fs::metadata(file)
+            .map_err(|e| PathIoError::Io(file.clone(), e))?
+            .len();
+    }
+    let available =
+        platform::available_space(existing).map_err(|e| PathIoError::Io(new_base.clone(), e))?;
+    if needed > available {
+        return Err(rejected(&format!(
+            "{needed} bytes are needed, but only {available} are available"
+        )));
+    }
+    fs::create_dir_all(&new_base).map_err(|e| PathIoError::Io(new_base.clone(), e))?;
+
+    let intent = Intent::Relocation {
+        old_base: old_base.clone(),
+        new_base: new_base.clone(),
+    };
+    recovery::guarded(intent, || {
+        if let Err(e) = io::copy_files(&old_base, &new_base, &files, progress, |_| {}) {
+            clear_dir(&new_base, None);
+            return Err(e);
+        }
+
+        let stamp = old_base.join(REDIRECT_FILE);
+        let redirect = Redirect {
+            target: new_base.clone(),
+        };
+        let text = ron::to_string(&redirect)
+            .map_err(|e| PathIoError::Format(stamp.clone(), e.to_string()))?;
+        if let Err(e) = io::write_atomic(&stamp, text.as_bytes()) {
+            clear_dir(&new_base, None);
+            return Err(e);
+        }
+        REDIRECTS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        resolve_cache::invalidate();
+
+        // Release the bookkeeping files below the old base path before deleting them.
+        recovery::close_log();
+        sync_state::unload();
+        clear_dir(&old_base, Some(&stamp));
+        Ok(new_base.clone())
+    })
+}
+
+/// Finishes an interrupted [`move_project_root`]: if the stamp was written, the old files are
+/// deleted, otherwise the copies are.
+pub(crate) fn recover_move(old_base: &Path, new_base: &Path) {
+    if read_stamp(old_base).as_deref() == Some(new_base) {
+        clear_dir(old_base, Some(&old_base.join(REDIRECT_FILE)));
+    } else if new_base.exists() {
+        clear_dir(new_base, None);
+    }
+}
+
+/// Deletes everything inside `dir` except `keep` and its parent directories, logging failures.
+fn clear_dir(dir: &Path, keep: Option<&Path>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!(
+                "Failed to clean up '{}': {}",
+                RedactedPath(dir),
+                redact(&e.to_string())
+            );
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        let result = match keep {
+            Some(keep) if keep == path => continue,
+            Some(keep) if is_dir && keep.starts_with(&path) => {
+                clear_dir(&path, Some(keep));
+                continue;
+            }
+            _ if is_dir => fs::remove_dir_all(&path),
+            _ => fs::remove_file(&path),
+        };
+        if let Err(e) = result {
+            warn!(
+                "Failed to clean up '{}': {}",
+                RedactedPath(&path),
+                redact(&e.to_string())
+            );
+        }
+    }
+}
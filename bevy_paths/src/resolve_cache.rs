@@ -0,0 +1,244 @@
+//! Optional caching of resolved paths for hot, dynamic templates.
+//!
+//! Streaming code resolves the same templates with the same parameters over and over
+//! (e.g. chunk coordinates). After [`enable_resolution_cache`] for a marker,
+//! [`resolve_cached`] returns a shared `Arc<Path>` from a bounded LRU cache keyed by the
+//! placeholder values, skipping substitution, validation and base path lookup.
+//! Changing the base path, the validation profile or the path limits empties all caches.
+//!
+//! ```rust,no_run
+//! # use bevy_paths::{prelude::*, resolve_cache};
+//! # use bevy_reflect::Reflect;
+//! #[derive(Path, Reflect, Default)]
+//! #[file("world/chunks/{x}_{y}.bin")]
+//! struct Chunk {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! resolve_cache::enable_resolution_cache::<Chunk>(4096);
+//! let path = resolve_cache::resolve_cached(&Chunk { x: 1, y: 2 }).unwrap();
+//! ```
+
+use {
+    crate::{PathValidationError, TypedPath, private::PathResolver, profile},
+    std::{
+        any::TypeId,
+        collections::HashMap,
+        path::Path,
+        sync::{
+            Arc, Mutex, RwLock,
+            atomic::{AtomicU64, Ordering},
+        },
+    },
+};
+
+/// Marks the end of the recency list.
+const NIL: usize = usize::MAX;
+
+struct Entry {
+    hash: u64,
+    /// The substituted placeholder values, compared on lookup as hashes can collide.
+    values: Vec<Option<String>>,
+    path: Arc<Path>,
+    /// The next more recently used entry.
+    newer: usize,
+    /// The next less recently used entry.
+    older: usize,
+}
+
+/// A least recently used cache with O(1) lookups, insertions and evictions.
+///
+/// Entries live in a `Vec` and form a doubly linked list by index, ordered by recency.
+/// They are found through a hash of their values, which only selects the candidates.
+pub(crate) struct LruCache {
+    capacity: usize,
+    buckets: HashMap<u64, Vec<usize>>,
+    entries: Vec<Entry>,
+    newest: usize,
+    oldest: usize,
+}
+
+impl LruCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            buckets: HashMap::new(),
+            entries: Vec::new(),
+            newest: NIL,
+            oldest: NIL,
+        }
+    }
+
+    /// The path cached for `values`. They are only computed if an entry has the same `hash`.
+    pub(crate) fn get(
+        &mut self,
+        hash: u64,
+        values: impl FnOnce() -> Vec<Option<String>>,
+    ) -> Option<Arc<Path>> {
+        let bucket = self.buckets.get(&hash)?;
+        let values = values();
+        let i = *bucket.iter().find(|&&i| self.entries[i].values == values)?;
+        self.unlink(i);
+        self.push_newest(i);
+        Some(self.entries[i].path.clone())
+    }
+
+    pub(crate) fn insert(&mut self, hash: u64, values: Vec<Option<String>>, path: Arc<Path>) {
+        let existing = self.buckets.get(&hash).and_then(|bucket| {
+            bucket
+                .iter()
+                .copied()
+                .find(|&i| self.entries[i].values == values)
+        });
+        if let Some(i) = existing {
+            self.entries[i].path = path;
+            self.unlink(i);
+            self.push_newest(i);
+            return;
+        }
+        let entry = Entry {
+            hash,
+            values,
+            path,
+            newer: NIL,
+            older: NIL,
+        };
+        let i = if self.entries.len() < self.capacity {
+            self.entries.push(entry);
+            self.entries.len() - 1
+        } else {
+            let i = self.oldest;
+            self.unlink(i);
+            self.remove_from_bucket(i);
+            self.entries[i] = entry;
+            i
+        };
+        self.buckets.entry(hash).or_default().push(i);
+        self.push_newest(i);
+    }
+
+    fn remove_from_bucket(&mut self, i: usize) {
+        let hash = self.entries[i].hash;
+        if let Some(bucket) = self.buckets.get_mut(&hash) {
+            bucket.retain(|&other| other != i);
+            if bucket.is_empty() {
+                self.buckets.remove(&hash);
+            }
+        }
+    }
+
+    fn unlink(&mut self, i: usize) {
+        let (newer, older) = (self.entries[i].newer, self.entries[i].older);
+        match newer {
+            NIL => self.newest = older,
+            newer => self.entries[newer].older = older,
+        }
+        match older {
+            NIL => self.oldest = newer,
+            older => self.entries[older].newer = newer,
+        }
+    }
+
+    fn push_newest(&mut self, i: usize) {
+        self.entries[i].newer = NIL;
+        self.entries[i].older = self.newest;
+        match self.newest {
+            NIL => self.oldest = i,
+            newest => self.entries[newest].newer = i,
+        }
+        self.newest = i;
+    }
+}
+
+/// The cache of a marker.
+struct MarkerCache {
+    /// The [`GENERATION`] the entries were resolved in.
+    generation: u64,
+    lru: LruCache,
+}
+
+impl MarkerCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            generation: GENERATION.load(Ordering::Relaxed),
+            lru: LruCache::new(capacity),
+        }
+    }
+
+    /// Drops all entries if the settings changed since they were resolved.
+    fn revalidate(&mut self) {
+        let generation = GENERATION.load(Ordering::Relaxed);
+        if self.generation != generation {
+            self.lru = LruCache::new(self.lru.capacity);
+            self.generation = generation;
+        }
+    }
+}
+
+/// Caches per marker.
+static CACHES: RwLock<Option<HashMap<TypeId, Arc<Mutex<MarkerCache>>>>> = RwLock::new(None);
+
+/// Incremented whenever a setting affecting resolution changes: the base path, the
+/// validation profile or the path limits.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Drops all cached paths, as they were resolved with outdated settings.
+pub(crate) fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Enables caching of resolved paths of `P`, keeping at most `capacity` entries.
+///
+/// Calling it again replaces the cache of `P` with an empty one.
+pub fn enable_resolution_cache<P: TypedPath>(capacity: usize) {
+    CACHES
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_default()
+        .insert(
+            TypeId::of::<P>(),
+            Arc::new(Mutex::new(MarkerCache::new(capacity.max(1)))),
+        );
+}
+
+/// Disables caching for `P` and drops its cached paths.
+pub fn disable_resolution_cache<P: TypedPath>() {
+    if let Some(caches) = CACHES.write().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        caches.remove(&TypeId::of::<P>());
+    }
+}
+
+/// Resolves `marker` like [`TypedPath::resolve`], using the cache of `P` if enabled.
+///
+/// Without a cache, or while [`profile::with_profile`] or [`profile::with_path_limits`] is
+/// active, the path is resolved on every call.
+pub fn resolve_cached<P: TypedPath>(marker: &P) -> Result<Arc<Path>, PathValidationError> {
+    let cache = CACHES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|caches| caches.get(&TypeId::of::<P>()).cloned());
+    let Some(cache) = cache.filter(|_| !profile::is_scoped()) else {
+        return Ok(Arc::from(marker.resolve()?));
+    };
+    let placeholders = P::placeholders();
+    let values = || PathResolver::placeholder_values(marker.as_reflect(), &placeholders);
+    let hash = PathResolver::placeholder_hash(marker.as_reflect(), &placeholders);
+    let generation = {
+        let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.revalidate();
+        if let Some(path) = cache.lru.get(hash, values) {
+            return Ok(path);
+        }
+        cache.generation
+    };
+    let path: Arc<Path> = Arc::from(marker.resolve()?);
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    cache.revalidate();
+    // The path is outdated if the settings changed while resolving it.
+    if cache.generation == generation {
+        cache.lru.insert(hash, values(), path.clone());
+    }
+    Ok(path)
+}
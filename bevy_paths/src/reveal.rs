@@ -0,0 +1,30 @@
+//! Opening managed directories in the OS file manager.
+
+use {
+    crate::{TypedPath, io::PathIoError, private::PathResolver},
+    std::{fs, io, path::Path},
+};
+
+/// Opens the directory of `marker` in Explorer, Finder or the `xdg-open` file manager.
+///
+/// The directory is created first if it does not exist yet, so an "Open save folder"
+/// button works before the first save.
+pub fn reveal<P: TypedPath>(marker: &P) -> Result<(), PathIoError> {
+    let dir = marker.resolve()?;
+    fs::create_dir_all(&dir).map_err(|e| PathIoError::Io(dir.clone(), e))?;
+    opener::open(&dir).map_err(|e| PathIoError::Io(dir, io::Error::other(e)))
+}
+
+/// Opens the OS file manager with `path` selected (Explorer `/select`, Finder reveal).
+///
+/// `path` must exist and lie inside the project base path.
+pub fn reveal_file(path: &Path) -> Result<(), PathIoError> {
+    let base = PathResolver::determine_base_path(None)?;
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| PathIoError::Io(path.to_path_buf(), e))?;
+    if !canonical.starts_with(&base) {
+        return Err(PathIoError::OutsideBasePath(canonical));
+    }
+    opener::reveal(&canonical).map_err(|e| PathIoError::Io(canonical, io::Error::other(e)))
+}
@@ -0,0 +1,78 @@
+//! Saving and loading [`DynamicScene`]s to managed paths.
+//!
+//! Scene files use Bevy's `.scn.ron` convention and go through [`io::write`] and [`io::read`],
+//! so they are written atomically and take part in IO statistics, sync state tracking,
+//! external modification detection and overlays like every other file.
+
+use {
+    crate::{TypedPath, io, io::PathIoError},
+    bevy_ecs::{entity::Entity, reflect::AppTypeRegistry, world::World},
+    bevy_reflect::TypeRegistry,
+    bevy_scene::{DynamicScene, DynamicSceneBuilder, serde::SceneDeserializer},
+    serde::de::DeserializeSeed,
+    std::path::PathBuf,
+};
+
+/// The file extension used for scene files.
+pub const SCENE_EXTENSION: &str = "scn.ron";
+
+/// Appends [`SCENE_EXTENSION`] to `name` unless it is already present.
+pub fn scene_file_name(name: &str) -> String {
+    if name.ends_with(&format!(".{SCENE_EXTENSION}")) {
+        name.to_string()
+    } else {
+        format!("{name}.{SCENE_EXTENSION}")
+    }
+}
+
+/// Serializes `scene` to `<name>.scn.ron` inside `marker` and returns the written path.
+pub fn save_scene<P: TypedPath>(
+    marker: &P,
+    name: &str,
+    scene: &DynamicScene,
+    registry: &TypeRegistry,
+) -> Result<PathBuf, PathIoError> {
+    let file_name = scene_file_name(name);
+    let path = io::resolve_in(marker, &file_name)?;
+    let text = scene
+        .serialize(registry)
+        .map_err(|e| PathIoError::Format(path.clone(), e.to_string()))?;
+    io::write(marker, &file_name, text.as_bytes())?;
+    Ok(path)
+}
+
+/// Extracts `entities` from `world` into a scene and saves it like [`save_scene`].
+///
+/// Resources are not included. Use [`DynamicSceneBuilder`] with [`save_scene`] for finer filtering.
+pub fn save_world_snapshot<P: TypedPath>(
+    marker: &P,
+    name: &str,
+    world: &World,
+    entities: impl Iterator<Item = Entity>,
+) -> Result<PathBuf, PathIoError> {
+    let scene = DynamicSceneBuilder::from_world(world)
+        .extract_entities(entities)
+        .build();
+    let registry = world.resource::<AppTypeRegistry>().read();
+    save_scene(marker, name, &scene, &registry)
+}
+
+/// Loads `<name>.scn.ron` from `marker` into a [`DynamicScene`].
+pub fn load_scene<P: TypedPath>(
+    marker: &P,
+    name: &str,
+    registry: &TypeRegistry,
+) -> Result<DynamicScene, PathIoError> {
+    let file_name = scene_file_name(name);
+    let path = io::resolve_in(marker, &file_name)?;
+    let bytes = io::read(marker, &file_name)?;
+    let text = std::str::from_utf8(&bytes)
+        .map_err(|e| PathIoError::Format(path.clone(), e.to_string()))?;
+    let mut deserializer = ron::de::Deserializer::from_str(text)
+        .map_err(|e| PathIoError::Format(path.clone(), e.to_string()))?;
+    SceneDeserializer {
+        type_registry: registry,
+    }
+    .deserialize(&mut deserializer)
+    .map_err(|e| PathIoError::Format(path, e.to_string()))
+}
@@ -0,0 +1,102 @@
+//! Ordering of directory listings for save/load UIs.
+//!
+//! [`io::list`](crate::io::list) returns the entries of a marker directory in a
+//! [`SortOrder`]; [`sort_paths`] applies the same ordering to any list of paths.
+
+use {
+    std::{cmp::Ordering, fs, path::PathBuf, time::SystemTime},
+    unicode_normalization::{UnicodeNormalization, char::is_combining_mark},
+};
+
+/// How listed entries are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// Byte-wise by file name.
+    Name,
+    /// By file name, comparing digit runs as numbers, so `save10` follows `save9`.
+    #[default]
+    Natural,
+    /// Like [`Natural`](Self::Natural), but ignoring case and accents (`Élan` sorts with `elan`),
+    /// which matches the collation of most Latin-script locales.
+    Collated,
+    /// Most recently modified first, then [`Natural`](Self::Natural).
+    NewestFirst,
+}
+
+/// Sorts `paths` by `order`.
+pub fn sort_paths(paths: &mut [PathBuf], order: SortOrder) {
+    let name = |path: &PathBuf| {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    };
+    match order {
+        SortOrder::Name => paths.sort_by_cached_key(name),
+        SortOrder::Natural => paths.sort_by_cached_key(|path| NaturalKey::new(&name(path))),
+        SortOrder::Collated => {
+            paths.sort_by_cached_key(|path| NaturalKey::new(&collation_form(&name(path))))
+        }
+        SortOrder::NewestFirst => paths.sort_by_cached_key(|path| {
+            let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+            (
+                std::cmp::Reverse(modified.unwrap_or(SystemTime::UNIX_EPOCH)),
+                NaturalKey::new(&name(path)),
+            )
+        }),
+    }
+}
+
+/// Compares `a` and `b` in [`SortOrder::Natural`].
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    NaturalKey::new(a).cmp(&NaturalKey::new(b))
+}
+
+/// Lowercase without combining marks.
+fn collation_form(name: &str) -> String {
+    name.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// A name split into text and number runs. Numbers compare by value, then by their leading
+/// zeros, so `7` < `07` < `8`.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct NaturalKey(Vec<Run>);
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Run {
+    Number {
+        digits: usize,
+        value: String,
+        zeros: usize,
+    },
+    Text(String),
+}
+
+impl NaturalKey {
+    fn new(name: &str) -> Self {
+        let mut runs = Vec::new();
+        let mut rest = name;
+        while let Some(first) = rest.chars().next() {
+            let is_digit = first.is_ascii_digit();
+            let end = rest
+                .find(|c: char| c.is_ascii_digit() != is_digit)
+                .unwrap_or(rest.len());
+            let (run, tail) = rest.split_at(end);
+            runs.push(if is_digit {
+                let value = run.trim_start_matches('0');
+                Run::Number {
+                    digits: value.len(),
+                    value: value.to_string(),
+                    zeros: run.len() - value.len(),
+                }
+            } else {
+                Run::Text(run.to_string())
+            });
+            rest = tail;
+        }
+        Self(runs)
+    }
+}
@@ -0,0 +1,89 @@
+//! Size and age of marker directories.
+//!
+//! [`stat`] walks a marker directory synchronously. Settings screens use the
+//! [`MarkerStatCache`] resource instead: [`MarkerStatCache::refresh`] computes the stat on the
+//! [`IoTaskPool`] and [`MarkerStatCache::get`] returns the last result, e.g. for
+//! "Saves: 34 files, 120 MB, last played yesterday".
+
+use {
+    crate::{TypedPath, io, io::PathIoError},
+    bevy_ecs::prelude::*,
+    bevy_tasks::{IoTaskPool, Task, TaskPool, block_on, futures_lite::future},
+    std::{any::TypeId, collections::HashMap, fs, time::SystemTime},
+};
+
+/// Disk usage of a marker directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MarkerStat {
+    /// The total size of all files in bytes.
+    pub size: u64,
+    /// The number of files.
+    pub file_count: usize,
+    /// The newest modification time of any file.
+    pub newest_modified: Option<SystemTime>,
+    /// When the directory was created, if the platform reports it.
+    pub created: Option<SystemTime>,
+}
+
+/// Computes the [`MarkerStat`] of the directory of `marker`. A missing directory is empty.
+pub fn stat<P: TypedPath>(marker: &P) -> Result<MarkerStat, PathIoError> {
+    let dir = marker.resolve()?;
+    let mut stat = MarkerStat {
+        created: fs::metadata(&dir).and_then(|m| m.created()).ok(),
+        ..Default::default()
+    };
+    for file in io::walk_files(&dir)? {
+        let metadata = fs::metadata(&file).map_err(|e| PathIoError::Io(file, e))?;
+        stat.size += metadata.len();
+        stat.file_count += 1;
+        stat.newest_modified = stat.newest_modified.max(metadata.modified().ok());
+    }
+    Ok(stat)
+}
+
+/// Cached [`MarkerStat`]s per marker type, computed in the background.
+///
+/// Inserted by [`PathsPlugin`](crate::PathsPlugin). For markers with placeholders, the
+/// instance passed to the last [`refresh`](Self::refresh) wins.
+#[derive(Resource, Default)]
+pub struct MarkerStatCache {
+    stats: HashMap<TypeId, Result<MarkerStat, String>>,
+    running: HashMap<TypeId, Task<Result<MarkerStat, String>>>,
+}
+
+impl MarkerStatCache {
+    /// Returns the last computed stat of `P`, or the error that stopped the computation.
+    pub fn get<P: TypedPath>(&self) -> Option<Result<&MarkerStat, &str>> {
+        self.stats
+            .get(&TypeId::of::<P>())
+            .map(|stat| stat.as_ref().map_err(String::as_str))
+    }
+
+    /// Recomputes the stat of `marker` on the [`IoTaskPool`], unless a refresh is running.
+    pub fn refresh<P: TypedPath + Clone>(&mut self, marker: &P) {
+        let key = TypeId::of::<P>();
+        if self.running.contains_key(&key) {
+            return;
+        }
+        let marker = marker.clone();
+        let pool = IoTaskPool::get_or_init(TaskPool::default);
+        let task = pool.spawn(async move { stat(&marker).map_err(|e| e.to_string()) });
+        self.running.insert(key, task);
+    }
+
+    /// Returns `true` while a refresh of `P` is running.
+    pub fn is_refreshing<P: TypedPath>(&self) -> bool {
+        self.running.contains_key(&TypeId::of::<P>())
+    }
+}
+
+pub(crate) fn poll_marker_stats(mut cache: ResMut<MarkerStatCache>) {
+    let MarkerStatCache { stats, running } = &mut *cache;
+    running.retain(|key, task| match block_on(future::poll_once(task)) {
+        Some(stat) => {
+            stats.insert(*key, stat);
+            false
+        }
+        None => true,
+    });
+}
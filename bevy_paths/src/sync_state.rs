@@ -0,0 +1,165 @@
+//! Tracking of files modified since the last cloud sync.
+//!
+//! Markers opt in with [`track_sync_state`]. The write helpers in [`io`](crate::io) then
+//! record every file they write inside them. Cloud-save integrations query
+//! [`modified_files`] to decide what to upload and call [`mark_synced`] afterwards.
+//!
+//! The state is kept below the base path, so it survives restarts: a snapshot in
+//! `.bevy_paths/sync_state.ron`, rewritten by [`mark_synced`], and a [`journal`] of the
+//! files modified since, so recording a write costs one append regardless of how many files
+//! are already tracked. If [`mark_synced`] is interrupted, files may be reported as modified
+//! again, never the other way round. Marker directories are stored relative to the base path,
+//! so the state stays valid when the base path is moved.
+
+use {
+    crate::{
+        TypedPath, display::redact, io, io::PathIoError, journal, journal::JournalFile,
+        private::PathResolver,
+    },
+    bevy_log::warn,
+    std::{
+        any::TypeId,
+        collections::{BTreeMap, BTreeSet},
+        fs,
+        path::Path,
+        sync::{Mutex, RwLock},
+    },
+};
+
+/// The location of the state snapshot, relative to the base path.
+pub const SYNC_STATE_FILE: &str = ".bevy_paths/sync_state.ron";
+
+/// The location of the journal of modifications since the snapshot, relative to the base path.
+pub const SYNC_JOURNAL_FILE: &str = ".bevy_paths/sync_state.journal";
+
+/// Modified files per marker directory, relative to the base path.
+type SyncState = BTreeMap<String, BTreeSet<String>>;
+
+struct Tracker {
+    state: SyncState,
+    journal: JournalFile,
+}
+
+static STATE: Mutex<Option<Tracker>> = Mutex::new(None);
+
+static TRACKED: RwLock<Vec<TypeId>> = RwLock::new(Vec::new());
+
+/// Records writes through the [`io`](crate::io) helpers inside `P` as modified.
+pub fn track_sync_state<P: TypedPath>() {
+    let mut tracked = TRACKED.write().unwrap_or_else(|e| e.into_inner());
+    if !tracked.contains(&TypeId::of::<P>()) {
+        tracked.push(TypeId::of::<P>());
+    }
+}
+
+/// Called by the write helpers. Failures are logged, as the file itself was written.
+pub(crate) fn record_write<P: TypedPath>(marker: &P, sub_path: &Path) {
+    let tracked = TRACKED.read().unwrap_or_else(|e| e.into_inner());
+    if !tracked.contains(&TypeId::of::<P>()) {
+        return;
+    }
+    drop(tracked);
+    if let Err(e) = mark_modified(marker, sub_path) {
+        warn!(
+            "Failed to record '{}' as modified: {}",
+            sub_path.display(),
+            redact(&e.to_string())
+        );
+    }
+}
+
+/// Records `sub_path` inside `marker` as modified.
+pub fn mark_modified<P: TypedPath>(
+    marker: &P,
+    sub_path: impl AsRef<Path>,
+) -> Result<(), PathIoError> {
+    let key = marker_key(marker)?;
+    let sub_path = io::sub_path_key(sub_path.as_ref());
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let tracker = loaded(&mut guard)?;
+    if !tracker
+        .state
+        .entry(key.clone())
+        .or_default()
+        .insert(sub_path.clone())
+    {
+        return Ok(());
+    }
+    let record = ron::to_string(&(key, sub_path))
+        .map_err(|e| PathIoError::Format(SYNC_JOURNAL_FILE.into(), e.to_string()))?;
+    tracker.journal.append(record.as_bytes())?;
+    tracker.journal.sync()
+}
+
+/// Returns the files inside `marker` modified since the last [`mark_synced`].
+pub fn modified_files<P: TypedPath>(marker: &P) -> Result<Vec<String>, PathIoError> {
+    let key = marker_key(marker)?;
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let tracker = loaded(&mut guard)?;
+    Ok(tracker
+        .state
+        .get(&key)
+        .map(|files| files.iter().cloned().collect())
+        .unwrap_or_default())
+}
+
+/// Returns `true` if any file inside `marker` was modified since the last [`mark_synced`].
+pub fn is_modified<P: TypedPath>(marker: &P) -> Result<bool, PathIoError> {
+    Ok(!modified_files(marker)?.is_empty())
+}
+
+/// Clears the modified files of `marker`.
+pub fn mark_synced<P: TypedPath>(marker: &P) -> Result<(), PathIoError> {
+    let key = marker_key(marker)?;
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let tracker = loaded(&mut guard)?;
+    if tracker.state.remove(&key).is_none() {
+        return Ok(());
+    }
+    let path = PathResolver::determine_base_path(None)?.join(SYNC_STATE_FILE);
+    let text = ron::ser::to_string_pretty(&tracker.state, ron::ser::PrettyConfig::default())
+        .map_err(|e| PathIoError::Format(path.clone(), e.to_string()))?;
+    io::write_atomic(&path, text.as_bytes())?;
+    tracker.journal.clear()
+}
+
+/// Closes the journal. The state is loaded again from the base path on the next access.
+pub(crate) fn unload() {
+    *STATE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn marker_key<P: TypedPath>(marker: &P) -> Result<String, PathIoError> {
+    let dir = marker.resolve()?;
+    let base = PathResolver::determine_base_path(None)?;
+    Ok(io::sub_path_key(dir.strip_prefix(&base).unwrap_or(&dir)))
+}
+
+fn loaded(guard: &mut Option<Tracker>) -> Result<&mut Tracker, PathIoError> {
+    if guard.is_none() {
+        *guard = Some(load()?);
+    }
+    Ok(guard.as_mut().expect("the tracker was just loaded"))
+}
+
+fn load() -> Result<Tracker, PathIoError> {
+    let base = PathResolver::determine_base_path(None)?;
+    let path = base.join(SYNC_STATE_FILE);
+    let mut state = match fs::read_to_string(&path) {
+        Ok(text) => ron::from_str(&text).map_err(|e| PathIoError::Format(path, e.to_string()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => SyncState::new(),
+        Err(e) => return Err(PathIoError::Io(path, e)),
+    };
+    let path = base.join(SYNC_JOURNAL_FILE);
+    for record in journal::replay_path(&path)? {
+        let record = record?;
+        let (key, sub_path): (String, String) = std::str::from_utf8(&record)
+            .map_err(|e| e.to_string())
+            .and_then(|text| ron::from_str(text).map_err(|e| e.to_string()))
+            .map_err(|e| PathIoError::Format(path.clone(), e))?;
+        state.entry(key).or_default().insert(sub_path);
+    }
+    Ok(Tracker {
+        state,
+        journal: JournalFile::open(path)?,
+    })
+}
@@ -95,3 +95,1646 @@ fn test_component_validation() {
     assert!(validate_component("CON").is_err());
     assert!(validate_component("lpt1").is_err());
 }
+
+#[test]
+fn test_backslash_separators() {
+    assert_eq!(
+        validate_structural_path("saves\\slot_1").unwrap(),
+        PathBuf::from("saves/slot_1")
+    );
+    assert!(matches!(
+        validate_structural_path_with("saves\\slot_1", SeparatorPolicy::Reject),
+        Err(PathValidationError::BackslashNotAllowed)
+    ));
+    assert!(validate_structural_path("..\\hack").is_err());
+}
+
+#[derive(Path, Reflect)]
+#[file("tests/strict_separators/{name}", separators = "reject")]
+struct StrictSeparators {
+    name: String,
+}
+
+#[test]
+fn test_backslash_separators_in_values() {
+    // Values are normalized like templates by default...
+    let level = DynamicLevel {
+        id: "world\\1".to_string(),
+    };
+    assert!(level.resolve().unwrap().ends_with("levels/world/1/map.dat"));
+
+    // ...and rejected for markers with `separators = "reject"`.
+    let strict = StrictSeparators {
+        name: "world\\1".to_string(),
+    };
+    assert!(matches!(
+        strict.resolve(),
+        Err(PathValidationError::BackslashNotAllowed)
+    ));
+    let strict = StrictSeparators {
+        name: "world".to_string(),
+    };
+    assert!(strict.resolve().is_ok());
+    assert!(matches!(
+        io::resolve_in(&strict, "slot\\1.sav"),
+        Err(PathIoError::Validation(
+            PathValidationError::BackslashNotAllowed
+        ))
+    ));
+}
+
+#[test]
+fn test_os_path_validation() {
+    let path = std::path::Path::new("saves").join("slot_1");
+    assert_eq!(validate_structural_os_path(&path).unwrap(), path);
+    assert!(validate_structural_os_path(std::path::Path::new("../escape")).is_err());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let non_utf8 = std::ffi::OsStr::from_bytes(b"saves/\xff.sav");
+        assert!(matches!(
+            validate_structural_os_path(non_utf8),
+            Err(PathValidationError::NonUtf8Path(_))
+        ));
+    }
+}
+
+#[derive(Reflect, Default)]
+struct ConfigDir;
+
+impl TypedPath for ConfigDir {
+    const TEMPLATE: &'static str = "tests/config";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_io_roundtrip() {
+    io::save_ron(&ConfigDir, "roundtrip.ron", &(1u32, "two".to_string())).unwrap();
+    let loaded: (u32, String) = io::load_ron(&ConfigDir, "roundtrip.ron").unwrap();
+    assert_eq!(loaded, (1, "two".to_string()));
+
+    // Sub paths can be any `AsRef<Path>`.
+    let nested = PathBuf::from("nested").join("roundtrip.bin");
+    io::write(&ConfigDir, &nested, b"bytes").unwrap();
+    assert_eq!(io::read(&ConfigDir, nested).unwrap(), b"bytes");
+
+    // Sub paths follow the template rules
+    assert!(io::resolve_in(&ConfigDir, "../escape.ron").is_err());
+    assert!(
+        io::read(&ConfigDir, "missing.ron")
+            .unwrap_err()
+            .is_not_found()
+    );
+}
+
+#[derive(
+    bevy_ecs::prelude::Resource, serde::Serialize, serde::Deserialize, Default, Debug, PartialEq,
+)]
+struct Volume(u8);
+
+#[derive(Reflect, Default)]
+struct PersistDir;
+
+impl TypedPath for PersistDir {
+    const TEMPLATE: &'static str = "tests/persist";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+fn persisted_volume_app(file_name: &'static str) -> bevy_app::App {
+    let _ = std::fs::remove_file(io::resolve_in(&PersistDir, file_name).unwrap());
+    let mut app = bevy_app::App::new();
+    app.persist_resource::<Volume, PersistDir>(file_name);
+    app
+}
+
+#[test]
+fn test_persist_resource_seeds_default() {
+    let mut app = persisted_volume_app("volume.ron");
+    // Loading is deferred to `PreStartup`.
+    assert!(app.world().get_resource::<Volume>().is_none());
+    app.update();
+    assert_eq!(app.world().resource::<Volume>().0, 0);
+}
+
+#[test]
+fn test_persist_resource_debounced_save() {
+    let mut app = persisted_volume_app("debounced.ron");
+    app.update();
+    app.world_mut().resource_mut::<Volume>().0 = 7;
+    app.update();
+    assert!(io::load_ron::<_, Volume>(&PersistDir, "debounced.ron").is_err());
+
+    std::thread::sleep(PERSIST_DEBOUNCE + std::time::Duration::from_millis(100));
+    app.update();
+    assert_eq!(
+        io::load_ron::<_, Volume>(&PersistDir, "debounced.ron").unwrap(),
+        Volume(7)
+    );
+
+    // A new app loads the saved value.
+    let mut app = bevy_app::App::new();
+    app.persist_resource::<Volume, PersistDir>("debounced.ron");
+    app.update();
+    assert_eq!(*app.world().resource::<Volume>(), Volume(7));
+}
+
+#[test]
+fn test_persist_resource_flushes_on_exit() {
+    let mut app = persisted_volume_app("exit.ron");
+    app.update();
+    app.world_mut().resource_mut::<Volume>().0 = 3;
+    app.world_mut().write_message(bevy_app::AppExit::Success);
+    app.update();
+    assert_eq!(
+        io::load_ron::<_, Volume>(&PersistDir, "exit.ron").unwrap(),
+        Volume(3)
+    );
+}
+
+#[derive(Reflect, Default)]
+struct RegisteredDir;
+
+impl TypedPath for RegisteredDir {
+    const TEMPLATE: &'static str = "tests/registered";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_register_with_options() {
+    let _ = std::fs::remove_dir_all(RegisteredDir.resolve().unwrap());
+    let defaults = std::env::temp_dir().join("bevy_paths_registered_defaults");
+    let mut app = bevy_app::App::new();
+    app.register_with::<RegisteredDir>(
+        RegistrationOptions::new()
+            .create_on_startup()
+            .persist_resource::<Volume>("volume.ron")
+            .overlay(&defaults)
+            .count_io(),
+    );
+    assert_eq!(
+        overlay::overlay_source::<RegisteredDir>(),
+        Some(defaults.clone())
+    );
+    assert!(io_stats::is_io_counted::<RegisteredDir>());
+    assert!(!RegisteredDir.resolve().unwrap().exists());
+
+    app.update();
+    assert!(RegisteredDir.resolve().unwrap().is_dir());
+    assert_eq!(app.world().resource::<Volume>().0, 0);
+}
+
+#[derive(Reflect, Default)]
+struct ReadOnlyDir;
+
+impl TypedPath for ReadOnlyDir {
+    const TEMPLATE: &'static str = "tests/read_only";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[derive(Reflect, Default)]
+struct RotatedDir;
+
+impl TypedPath for RotatedDir {
+    const TEMPLATE: &'static str = "tests/rotated";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[derive(Reflect, Default)]
+struct QuotaDir;
+
+impl TypedPath for QuotaDir {
+    const TEMPLATE: &'static str = "tests/quota";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_registration_policies() {
+    for dir in [
+        ReadOnlyDir.resolve().unwrap(),
+        RotatedDir.resolve().unwrap(),
+        QuotaDir.resolve().unwrap(),
+    ] {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    let mut app = bevy_app::App::new();
+    app.register_with::<ReadOnlyDir>(
+        RegistrationOptions::new()
+            .readonly(true)
+            .category(PathCategory::Cache),
+    )
+    .register_with::<RotatedDir>(RegistrationOptions::new().keep_last(2))
+    .register_with::<QuotaDir>(RegistrationOptions::new().quota(10));
+
+    assert_eq!(
+        registration::category::<ReadOnlyDir>(),
+        Some(PathCategory::Cache)
+    );
+    assert_eq!(registration::category::<RotatedDir>(), None);
+    assert!(matches!(
+        io::write(&ReadOnlyDir, "a.bin", b"data"),
+        Err(PathIoError::ReadOnly(..))
+    ));
+    assert!(matches!(
+        journal::Journal::open(&ReadOnlyDir, "log.journal"),
+        Err(PathIoError::ReadOnly(..))
+    ));
+    assert!(!ReadOnlyDir.resolve().unwrap().exists());
+
+    for name in ["a.log", "b.log", "c.log"] {
+        io::write(&RotatedDir, name, b"log").unwrap();
+    }
+    let kept: Vec<_> = io::list(&RotatedDir, "", sort::SortOrder::Name)
+        .unwrap()
+        .iter()
+        .map(|path| PathBuf::from(path.file_name().unwrap()))
+        .collect();
+    assert_eq!(kept, vec![PathBuf::from("b.log"), PathBuf::from("c.log")]);
+
+    io::write(&QuotaDir, "a.bin", &[0; 6]).unwrap();
+    assert!(matches!(
+        io::write(&QuotaDir, "b.bin", &[0; 6]),
+        Err(PathIoError::QuotaExceeded(_, 10))
+    ));
+    // Replacing a file only counts the difference.
+    io::write(&QuotaDir, "a.bin", &[0; 10]).unwrap();
+}
+
+#[derive(Reflect, Default)]
+struct RecordingsDir;
+
+impl TypedPath for RecordingsDir {
+    const TEMPLATE: &'static str = "tests/recordings";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_recording_lifecycle() {
+    let mut recording = recording::Recording::start(RecordingsDir, "session").unwrap();
+    recording.write_frame(b"first").unwrap();
+    recording.write_frame(b"second").unwrap();
+    let info = recording.finalize().unwrap();
+
+    assert!(
+        recording::list_recordings(&RecordingsDir)
+            .unwrap()
+            .contains(&info)
+    );
+    let frames = recording::read_frames(&RecordingsDir, &info.file_name)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(frames, vec![b"first".to_vec(), b"second".to_vec()]);
+
+    // Recordings started within the same millisecond get distinct files.
+    let first = recording::Recording::start(RecordingsDir, "burst").unwrap();
+    let second = recording::Recording::start(RecordingsDir, "burst").unwrap();
+    assert_ne!(first.info().file_name, second.info().file_name);
+    for info in [first.finalize().unwrap(), second.finalize().unwrap()] {
+        recording::delete_recording(&RecordingsDir, &info.file_name).unwrap();
+    }
+
+    recording::delete_recording(&RecordingsDir, &info.file_name).unwrap();
+    assert!(
+        !recording::list_recordings(&RecordingsDir)
+            .unwrap()
+            .contains(&info)
+    );
+}
+
+#[test]
+fn test_staged_download_verification() {
+    let _ =
+        std::fs::remove_file(io::resolve_in(&ConfigDir, "downloads/partial/pack.part").unwrap());
+    let mut download = download::StagedDownload::open(ConfigDir, "pack").unwrap();
+    download.write(b"hello").unwrap();
+    drop(download);
+
+    // Resumes where the previous session stopped
+    let mut download = download::StagedDownload::open(ConfigDir, "pack").unwrap();
+    assert_eq!(download.resume_offset(), 5);
+    download.write(b" world").unwrap();
+
+    let verification = download::DownloadVerification {
+        size: Some(11),
+        blake3: Some(blake3::hash(b"hello world").to_hex().to_string()),
+    };
+    let path = download.finalize("packs/pack.bin", &verification).unwrap();
+    assert_eq!(std::fs::read(path).unwrap(), b"hello world");
+
+    let mut download = download::StagedDownload::open(ConfigDir, "broken").unwrap();
+    download.write(b"short").unwrap();
+    let verification = download::DownloadVerification {
+        size: Some(100),
+        blake3: None,
+    };
+    assert!(
+        download
+            .finalize("packs/broken.bin", &verification)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_protected_base_path_rejected() {
+    if cfg!(unix) {
+        assert!(matches!(
+            private::PathResolver::determine_base_path(Some(std::path::Path::new("/usr"))),
+            Err(PathValidationError::BasePathInProtectedLocation(_))
+        ));
+    }
+    assert!(private::PathResolver::determine_base_path(None).is_ok());
+}
+
+#[test]
+fn test_protected_exe_dir_falls_back_to_user_data_dir() {
+    if cfg!(unix) {
+        assert!(private::PathResolver::is_protected_location(
+            std::path::Path::new("/usr/games")
+        ));
+    }
+    let Some(dir) = platform::user_data_dir(std::ffi::OsStr::new("mygame")) else {
+        return;
+    };
+    assert!(dir.is_absolute());
+    assert!(dir.ends_with("mygame"));
+    assert!(!private::PathResolver::is_protected_location(&dir));
+}
+
+#[test]
+fn test_probe_writable_base_path() {
+    let base = ConfigDir.resolve().unwrap();
+    std::fs::create_dir_all(&base).unwrap();
+    assert_eq!(probe::probe_write_access(&base), None);
+}
+
+#[test]
+fn test_probe_cloud_synced_base_path() {
+    let dropbox = ConfigDir.resolve().unwrap().join("dropbox");
+    std::fs::create_dir_all(dropbox.join("game")).unwrap();
+    assert_eq!(probe::probe_cloud_sync(&dropbox.join("game")), None);
+
+    std::fs::write(dropbox.join(".dropbox"), b"{}").unwrap();
+    assert_eq!(
+        probe::probe_cloud_sync(&dropbox.join("game")),
+        Some(PathWarning::CloudSynced {
+            base_path: dropbox.join("game"),
+            provider: probe::CloudProvider::Dropbox,
+        })
+    );
+    let warning = probe::probe_cloud_sync(&dropbox.join("game")).unwrap();
+    assert!(warning.to_string().contains("is synced by Dropbox"));
+    std::fs::remove_dir_all(&dropbox).unwrap();
+
+    let icloud =
+        std::path::Path::new("/Users/anna/Library/Mobile Documents/com~apple~CloudDocs/game");
+    assert_eq!(
+        probe::cloud_provider(icloud),
+        Some(probe::CloudProvider::ICloud)
+    );
+}
+
+#[test]
+fn test_macos_application_support_fallback() {
+    let home = std::path::Path::new("/Users/player");
+    assert_eq!(
+        platform::application_support_dir(
+            std::path::Path::new("/Applications/MyGame.app/Contents/MacOS"),
+            home
+        ),
+        Some(PathBuf::from(
+            "/Users/player/Library/Application Support/MyGame"
+        ))
+    );
+    assert_eq!(
+        platform::application_support_dir(std::path::Path::new("/opt/mygame/bin"), home),
+        None
+    );
+}
+
+#[test]
+fn test_maintenance_job_runs_in_background() {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    let runs = Arc::new(AtomicUsize::new(0));
+    let counter = runs.clone();
+    let mut app = bevy_app::App::new();
+    app.add_maintenance_job("count", std::time::Duration::ZERO, move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Ok("counted".to_string())
+    });
+    for _ in 0..100 {
+        app.update();
+        if runs.load(Ordering::SeqCst) > 0 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert!(runs.load(Ordering::SeqCst) > 0);
+}
+
+#[derive(Reflect, Default)]
+struct CopySourceDir;
+
+impl TypedPath for CopySourceDir {
+    const TEMPLATE: &'static str = "tests/copy_source";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[derive(Reflect, Default)]
+struct ConfigBackupDir;
+
+impl TypedPath for ConfigBackupDir {
+    const TEMPLATE: &'static str = "tests/config_backup";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_copy_marker_reports_progress() {
+    let _ = std::fs::remove_dir_all(CopySourceDir.resolve().unwrap());
+    let _ = std::fs::remove_dir_all(ConfigBackupDir.resolve().unwrap());
+    io::write(&CopySourceDir, "copy/a.txt", b"12345").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(
+        std::env::temp_dir(),
+        io::resolve_in(&CopySourceDir, "outside").unwrap(),
+    )
+    .unwrap();
+
+    let mut tracker = ProgressTracker::default();
+    let handle = tracker.start();
+    io::copy_marker(&CopySourceDir, &ConfigBackupDir, &handle).unwrap();
+
+    let progress = handle.progress();
+    assert!(progress.finished);
+    assert_eq!(progress.bytes_done, 5);
+    assert_eq!(progress.bytes_done, progress.bytes_total);
+    assert_eq!(io::read(&ConfigBackupDir, "copy/a.txt").unwrap(), b"12345");
+    assert!(
+        !io::resolve_in(&ConfigBackupDir, "outside")
+            .unwrap()
+            .exists()
+    );
+
+    io::write(&CopySourceDir, "copy/a.txt", b"67890").unwrap();
+    let cancelled = tracker.start();
+    cancelled.cancel();
+    assert!(matches!(
+        io::copy_marker(&CopySourceDir, &ConfigBackupDir, &cancelled),
+        Err(PathIoError::Cancelled)
+    ));
+    // The cancelled copy leaves the previous file intact and no temporary file behind.
+    assert_eq!(io::read(&ConfigBackupDir, "copy/a.txt").unwrap(), b"12345");
+    assert!(
+        !io::resolve_in(&ConfigBackupDir, "copy/a.txt.tmp")
+            .unwrap()
+            .exists()
+    );
+}
+
+#[derive(Reflect, Default)]
+struct ManifestDir;
+
+impl TypedPath for ManifestDir {
+    const TEMPLATE: &'static str = "tests/manifest";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_manifest_detects_changes() {
+    let _ = std::fs::remove_dir_all(ManifestDir.resolve().unwrap());
+    io::write(&ManifestDir, "data/a.bin", b"a").unwrap();
+    io::write(&ManifestDir, "b.bin", b"b").unwrap();
+    manifest::build_manifest(&ManifestDir).unwrap();
+    assert!(manifest::verify_manifest(&ManifestDir).unwrap().is_clean());
+
+    io::write(&ManifestDir, "data/a.bin", b"changed").unwrap();
+    io::write(&ManifestDir, "c.bin", b"c").unwrap();
+    std::fs::remove_file(io::resolve_in(&ManifestDir, "b.bin").unwrap()).unwrap();
+    let report = manifest::verify_manifest(&ManifestDir).unwrap();
+    assert_eq!(report.modified, vec!["data/a.bin".to_string()]);
+    assert_eq!(report.added, vec!["c.bin".to_string()]);
+    assert_eq!(report.removed, vec!["b.bin".to_string()]);
+}
+
+/// A keyed hash standing in for a real signature scheme.
+struct TestSigner([u8; 32]);
+
+impl manifest::ManifestSigner for TestSigner {
+    fn sign(&self, manifest: &[u8]) -> Vec<u8> {
+        blake3::keyed_hash(&self.0, manifest).as_bytes().to_vec()
+    }
+}
+
+impl manifest::ManifestVerifier for TestSigner {
+    fn verify(&self, manifest: &[u8], signature: &[u8]) -> bool {
+        blake3::keyed_hash(&self.0, manifest).as_bytes() == signature
+    }
+}
+
+#[derive(Reflect, Default)]
+struct SignedDir;
+
+impl TypedPath for SignedDir {
+    const TEMPLATE: &'static str = "tests/signed";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_signed_manifest() {
+    io::write(&SignedDir, "content.bin", b"content").unwrap();
+    manifest::build_signed_manifest(&SignedDir, &TestSigner([1; 32])).unwrap();
+    assert!(
+        manifest::verify_signed_manifest(&SignedDir, &TestSigner([1; 32]))
+            .unwrap()
+            .is_clean()
+    );
+    assert!(matches!(
+        manifest::verify_signed_manifest(&SignedDir, &TestSigner([2; 32])),
+        Err(PathIoError::VerificationFailed(..))
+    ));
+}
+
+#[test]
+fn test_sync_conflict_names() {
+    assert_eq!(
+        conflict::original_name("save (Laptop's conflicted copy 2024-05-01).sav"),
+        Some("save.sav".to_string())
+    );
+    assert_eq!(
+        conflict::original_name("save.sync-conflict-20240501-120000-ABCDEFG.sav"),
+        Some("save.sav".to_string())
+    );
+    assert_eq!(conflict::original_name("save (1).sav"), None);
+    assert_eq!(conflict::original_name("save.sav"), None);
+
+    // The conflict suffix is matched from the right.
+    assert_eq!(
+        conflict::original_name("save (1) (Laptop's conflicted copy 2024-05-01).sav"),
+        Some("save (1).sav".to_string())
+    );
+    assert_eq!(
+        conflict::original_name("save (conflicted copy 2024-05-01 120000).sav"),
+        Some("save.sav".to_string())
+    );
+
+    // OneDrive appends the name of the machine.
+    let host = Some("GAMING-PC");
+    assert_eq!(
+        conflict::original_name_on("save-GAMING-PC.sav", host),
+        Some("save.sav".to_string())
+    );
+    assert_eq!(
+        conflict::original_name_on("quick-save-gaming-pc", host),
+        Some("quick-save".to_string())
+    );
+    assert_eq!(conflict::original_name_on("quick-save.sav", host), None);
+    assert_eq!(conflict::original_name_on("-GAMING-PC.sav", host), None);
+    assert_eq!(conflict::original_name_on("save-GAMING-PC.sav", None), None);
+}
+
+#[derive(Reflect, Default)]
+struct ConflictDir;
+
+impl TypedPath for ConflictDir {
+    const TEMPLATE: &'static str = "tests/conflicts";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_sync_conflict_keep_both() {
+    let _ = std::fs::remove_dir_all(ConflictDir.resolve().unwrap());
+    io::write(&ConflictDir, "slot.sav", b"local").unwrap();
+    io::write(&ConflictDir, "slot (PC's conflicted copy).sav", b"remote").unwrap();
+
+    let conflicts = conflict::detect_conflicts(&ConflictDir).unwrap();
+    assert_eq!(conflicts.len(), 1);
+    let kept =
+        conflict::resolve_conflict(&conflicts[0], conflict::ConflictResolution::KeepBoth).unwrap();
+    assert!(kept.ends_with("slot (conflict 1).sav"));
+    assert!(conflict::detect_conflicts(&ConflictDir).unwrap().is_empty());
+}
+
+#[derive(Reflect, Default)]
+struct NoConflictDir;
+
+impl TypedPath for NoConflictDir {
+    const TEMPLATE: &'static str = "tests/no_conflicts";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_sync_conflict_requires_original() {
+    let _ = std::fs::remove_dir_all(NoConflictDir.resolve().unwrap());
+    let host = std::env::var("COMPUTERNAME").unwrap_or_else(|_| "steamdeck".to_string());
+    io::write(&NoConflictDir, format!("profile-{host}.ron"), b"user").unwrap();
+    io::write(&NoConflictDir, "slot (PC's conflicted copy).sav", b"remote").unwrap();
+
+    assert!(
+        conflict::detect_conflicts(&NoConflictDir)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[derive(Reflect, Default)]
+struct SyncedDir;
+
+impl TypedPath for SyncedDir {
+    const TEMPLATE: &'static str = "tests/synced";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_sync_state_tracks_writes() {
+    sync_state::track_sync_state::<SyncedDir>();
+    sync_state::mark_synced(&SyncedDir).unwrap();
+    assert!(!sync_state::is_modified(&SyncedDir).unwrap());
+
+    io::write(&SyncedDir, "slot_1.sav", b"data").unwrap();
+    assert_eq!(
+        sync_state::modified_files(&SyncedDir).unwrap(),
+        vec!["slot_1.sav".to_string()]
+    );
+
+    sync_state::mark_synced(&SyncedDir).unwrap();
+    assert!(!sync_state::is_modified(&SyncedDir).unwrap());
+
+    // Markers that are not tracked are not recorded.
+    io::write(&ConfigDir, "untracked.txt", b"data").unwrap();
+    assert!(
+        !sync_state::modified_files(&ConfigDir)
+            .unwrap()
+            .contains(&"untracked.txt".to_string())
+    );
+}
+
+#[derive(Reflect, Default)]
+struct WatchedDir;
+
+impl TypedPath for WatchedDir {
+    const TEMPLATE: &'static str = "tests/watched";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+fn external_modifications(app: &mut bevy_app::App) -> Vec<PathBuf> {
+    app.update();
+    app.world_mut()
+        .resource_mut::<bevy_ecs::message::Messages<FileExternallyModified<WatchedDir>>>()
+        .drain()
+        .map(|message| message.path)
+        .collect()
+}
+
+#[test]
+fn test_external_modification_detection() {
+    let _ = std::fs::remove_dir_all(WatchedDir.resolve().unwrap());
+    // Files are only recorded once a marker is watched.
+    let mut app = bevy_app::App::new();
+    app.detect_external_modifications::<WatchedDir>();
+    io::write(&WatchedDir, "settings.ron", b"()").unwrap();
+    let mut journal = journal::Journal::open(&WatchedDir, "events.journal").unwrap();
+    journal.append(b"opened").unwrap();
+    let mut pool = FileHandlePool::new(1);
+    pool.append(&WatchedDir, "log.txt", b"line\n").unwrap();
+
+    // Writes through the helpers, including open append handles, are not external.
+    journal.append(b"appended").unwrap();
+    journal.sync().unwrap();
+    pool.append(&WatchedDir, "log.txt", b"line\n").unwrap();
+    pool.flush().unwrap();
+    io::write(&WatchedDir, "settings.ron", b"(volume: 1)").unwrap();
+    assert!(external_modifications(&mut app).is_empty());
+
+    // Closing the handles records their final state.
+    drop(journal);
+    drop(pool);
+    let mut app = bevy_app::App::new();
+    app.detect_external_modifications::<WatchedDir>();
+    assert!(external_modifications(&mut app).is_empty());
+
+    // A write that bypasses the helpers is reported once.
+    let settings = io::resolve_in(&WatchedDir, "settings.ron").unwrap();
+    std::fs::write(&settings, b"(volume: 100, edited: true)").unwrap();
+    let mut app = bevy_app::App::new();
+    app.detect_external_modifications::<WatchedDir>();
+    assert_eq!(external_modifications(&mut app), vec![settings]);
+    let mut app = bevy_app::App::new();
+    app.detect_external_modifications::<WatchedDir>();
+    assert!(external_modifications(&mut app).is_empty());
+}
+
+#[test]
+fn test_file_handle_pool_evicts_lru() {
+    let mut pool = FileHandlePool::new(2);
+    let _ = std::fs::remove_file(io::resolve_in(&ConfigDir, "logs/a.log").unwrap());
+    pool.append(&ConfigDir, "logs/a.log", b"1").unwrap();
+    pool.append(&ConfigDir, "logs/b.log", b"2").unwrap();
+    pool.append(&ConfigDir, "logs/a.log", b"3").unwrap();
+    pool.append(&ConfigDir, "logs/c.log", b"4").unwrap();
+    assert_eq!(pool.len(), 2);
+
+    pool.flush().unwrap();
+    assert_eq!(io::read(&ConfigDir, "logs/a.log").unwrap(), b"13");
+}
+
+#[cfg(feature = "memmap")]
+#[test]
+fn test_mmap_reads_file() {
+    io::write(&ConfigDir, "mmap_chunk.bin", &[1, 2, 3]).unwrap();
+    // SAFETY: No other test touches this file, so it is not modified while mapped.
+    let view = unsafe { io::mmap(&ConfigDir, "mmap_chunk.bin") }.unwrap();
+    assert_eq!(&view[..], &[1, 2, 3]);
+}
+
+#[test]
+fn test_journal_replay_ignores_torn_record() {
+    let path = io::resolve_in(&ConfigDir, "undo.journal").unwrap();
+    let _ = std::fs::remove_file(&path);
+    {
+        let mut journal = journal::Journal::open(&ConfigDir, "undo.journal").unwrap();
+        journal.append(b"one").unwrap();
+        journal.append(b"two").unwrap();
+    }
+    // Simulate a crash in the middle of a record
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes.extend_from_slice(&[10, 0, 0, 0, b'x']);
+    std::fs::write(&path, bytes).unwrap();
+
+    let replay = || -> Vec<Vec<u8>> {
+        journal::replay(&ConfigDir, "undo.journal")
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    };
+    assert_eq!(replay(), vec![b"one".to_vec(), b"two".to_vec()]);
+
+    // Appending after the crash continues after the last complete record
+    let mut journal = journal::Journal::open(&ConfigDir, "undo.journal").unwrap();
+    journal.append(b"three").unwrap();
+    drop(journal);
+    assert_eq!(
+        replay(),
+        vec![b"one".to_vec(), b"two".to_vec(), b"three".to_vec()]
+    );
+
+    // A corrupt length never allocates more than the file holds
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+    std::fs::write(&path, bytes).unwrap();
+    assert_eq!(replay().len(), 3);
+}
+
+#[test]
+fn test_journal_rejects_damaged_record_in_the_middle() {
+    let path = io::resolve_in(&ConfigDir, "damaged.journal").unwrap();
+    let _ = std::fs::remove_file(&path);
+    {
+        let mut journal = journal::Journal::open(&ConfigDir, "damaged.journal").unwrap();
+        journal.append(b"one").unwrap();
+        journal.append(b"two").unwrap();
+        journal.append(b"three").unwrap();
+    }
+    // Flip a bit in the data of the second record
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[11 + 8] ^= 1;
+    std::fs::write(&path, &bytes).unwrap();
+
+    let records: Vec<_> = journal::replay(&ConfigDir, "damaged.journal")
+        .unwrap()
+        .collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].as_ref().unwrap(), b"one");
+    assert!(matches!(records[1], Err(PathIoError::Format(..))));
+
+    // Opening fails and keeps the records after the damaged one
+    assert!(matches!(
+        journal::Journal::open(&ConfigDir, "damaged.journal"),
+        Err(PathIoError::Format(..))
+    ));
+    assert_eq!(std::fs::read(&path).unwrap(), bytes);
+}
+
+#[derive(Reflect, Default)]
+struct WorldDir;
+
+impl TypedPath for WorldDir {
+    const TEMPLATE: &'static str = "tests/world";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_wal_save_compaction() {
+    let _ = std::fs::remove_dir_all(WorldDir.resolve().unwrap());
+    let mut save = wal::WalSave::open(WorldDir, "world").unwrap();
+    save.append_delta(b"+a").unwrap();
+    save.append_delta(b"+b").unwrap();
+    assert_eq!(
+        save.state().unwrap(),
+        wal::WalState {
+            base: None,
+            deltas: vec![b"+a".to_vec(), b"+b".to_vec()],
+        }
+    );
+
+    save.compact(b"ab").unwrap();
+    save.append_delta(b"+c").unwrap();
+    drop(save);
+
+    let save = wal::WalSave::open(WorldDir, "world").unwrap();
+    assert_eq!(
+        save.state().unwrap(),
+        wal::WalState {
+            base: Some(b"ab".to_vec()),
+            deltas: vec![b"+c".to_vec()],
+        }
+    );
+}
+
+#[derive(Reflect, Default)]
+struct WalCrashDir;
+
+impl TypedPath for WalCrashDir {
+    const TEMPLATE: &'static str = "tests/wal_crash";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_wal_save_crash_recovery() {
+    let _ = std::fs::remove_dir_all(WalCrashDir.resolve().unwrap());
+    let mut save = wal::WalSave::open(WalCrashDir, "world").unwrap();
+    save.append_delta(b"+a").unwrap();
+    drop(save);
+
+    // Crash after writing the compacted base, before the journal was reset
+    let mut base = 1u64.to_le_bytes().to_vec();
+    base.extend_from_slice(b"a");
+    io::write(&WalCrashDir, "world.base", &base).unwrap();
+    let mut save = wal::WalSave::open(WalCrashDir, "world").unwrap();
+    assert_eq!(
+        save.state().unwrap(),
+        wal::WalState {
+            base: Some(b"a".to_vec()),
+            deltas: Vec::new(),
+        }
+    );
+
+    // Crash in the middle of appending a delta
+    save.append_delta(b"+b").unwrap();
+    drop(save);
+    let wal = io::resolve_in(&WalCrashDir, "world.wal").unwrap();
+    let mut bytes = std::fs::read(&wal).unwrap();
+    bytes.extend_from_slice(&[2, 0, 0, 0, 1, 2]);
+    std::fs::write(&wal, bytes).unwrap();
+    let mut save = wal::WalSave::open(WalCrashDir, "world").unwrap();
+    save.append_delta(b"+c").unwrap();
+    assert_eq!(
+        save.state().unwrap().deltas,
+        vec![b"+b".to_vec(), b"+c".to_vec()]
+    );
+}
+
+#[derive(Reflect, Default)]
+struct SlotsDir;
+
+impl TypedPath for SlotsDir {
+    const TEMPLATE: &'static str = "tests/slots";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_dir_save_swap_and_recovery() {
+    let _ = std::fs::remove_dir_all(SlotsDir.resolve().unwrap());
+    for content in ["first", "second"] {
+        let staging = dir_save::begin_dir_save(&SlotsDir, "slot_3").unwrap();
+        std::fs::write(staging.join("world.dat"), content).unwrap();
+        dir_save::commit_dir_save(&SlotsDir, "slot_3").unwrap();
+    }
+    assert_eq!(io::read(&SlotsDir, "slot_3/world.dat").unwrap(), b"second");
+
+    // Crash after moving the old save away but before moving the new one in
+    let slot = io::resolve_in(&SlotsDir, "slot_3").unwrap();
+    std::fs::rename(&slot, io::resolve_in(&SlotsDir, "slot_3.bak").unwrap()).unwrap();
+    dir_save::recover_dir_save(&SlotsDir, "slot_3").unwrap();
+    assert_eq!(io::read(&SlotsDir, "slot_3/world.dat").unwrap(), b"second");
+}
+
+#[derive(Reflect, Default)]
+struct MigratedDir;
+
+impl TypedPath for MigratedDir {
+    const TEMPLATE: &'static str = "tests/migrated";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[derive(serde::Deserialize)]
+struct SettingsV1 {
+    windowed: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+struct SettingsV2 {
+    version: u32,
+    fullscreen: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+enum WindowMode {
+    Windowed(u32),
+    Fullscreen,
+}
+
+#[derive(serde::Deserialize)]
+struct DisplaySettingsV0 {
+    window: WindowMode,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+struct DisplaySettings {
+    version: u32,
+    mode: WindowMode,
+}
+
+#[test]
+fn test_migration_on_load() {
+    migration::register_migration::<MigratedDir, _, _>("settings.ron", 1, |old: SettingsV1| {
+        Ok(SettingsV2 {
+            version: 2,
+            fullscreen: !old.windowed,
+        })
+    });
+    migration::register_migration::<MigratedDir, _, _>(
+        "display.ron",
+        0,
+        |old: DisplaySettingsV0| {
+            Ok(DisplaySettings {
+                version: 1,
+                mode: old.window,
+            })
+        },
+    );
+
+    io::write(
+        &MigratedDir,
+        "settings.ron",
+        b"(version: 1, windowed: false)",
+    )
+    .unwrap();
+    let settings: SettingsV2 = io::load_ron(&MigratedDir, "settings.ron").unwrap();
+    assert_eq!(
+        settings,
+        SettingsV2 {
+            version: 2,
+            fullscreen: true
+        }
+    );
+
+    // Files that do not match the pattern are not migrated.
+    io::write(&MigratedDir, "other.ron", b"(version: 1)").unwrap();
+    assert!(io::load_ron::<_, SettingsV2>(&MigratedDir, "other.ron").is_err());
+    io::write(&MigratedDir, "index.ron", b"[1, 2]").unwrap();
+    assert_eq!(
+        io::load_ron::<_, Vec<u32>>(&MigratedDir, "index.ron").unwrap(),
+        vec![1, 2]
+    );
+
+    // Files of the current version are loaded directly.
+    io::write(
+        &MigratedDir,
+        "display.ron",
+        b"(version: 1, mode: Windowed(1280))",
+    )
+    .unwrap();
+    assert_eq!(
+        io::load_ron::<_, DisplaySettings>(&MigratedDir, "display.ron").unwrap(),
+        DisplaySettings {
+            version: 1,
+            mode: WindowMode::Windowed(1280)
+        }
+    );
+    // Enums survive a migration.
+    io::write(
+        &MigratedDir,
+        "display.ron",
+        b"(version: 0, window: Fullscreen)",
+    )
+    .unwrap();
+    assert_eq!(
+        io::load_ron::<_, DisplaySettings>(&MigratedDir, "display.ron").unwrap(),
+        DisplaySettings {
+            version: 1,
+            mode: WindowMode::Fullscreen
+        }
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_display_path() {
+    let home = std::path::Path::new("/home/player");
+    let path = std::path::Path::new("/home/player/.local/share/game/saves/slot_1.sav");
+    assert_eq!(
+        display::format_path(path, Some(home), None),
+        "~/.local/share/game/saves/slot_1.sav"
+    );
+    assert_eq!(
+        display::format_path(path, Some(home), Some(20)),
+        "~/.local/s…lot_1.sav"
+    );
+    assert_eq!(
+        display::format_path(std::path::Path::new("/home/playerx"), Some(home), None),
+        "/home/playerx"
+    );
+}
+
+#[test]
+fn test_redact_user_paths() {
+    assert_eq!(
+        display::redact_with(
+            "Failed on '/home/anna/game/save.sav'",
+            Some("/home/anna"),
+            Some("anna")
+        ),
+        "Failed on '<home>/game/save.sav'"
+    );
+    assert_eq!(
+        display::redact_with(r"D:\Users\anna\game", None, Some("anna")),
+        r"D:\Users\<user>\game"
+    );
+    assert_eq!(
+        display::redact_with("annabelle/anna", None, Some("anna")),
+        "annabelle/<user>"
+    );
+    assert_eq!(
+        display::redact_with(
+            "'/home/annabelle/save' and '/backup/home/anna' and /home/anna",
+            Some("/home/anna"),
+            None
+        ),
+        "'/home/annabelle/save' and '/backup/home/anna' and <home>"
+    );
+}
+
+#[test]
+fn test_install_root() {
+    let exe_dir = std::path::Path::new("/Applications/My Game.app/Contents/MacOS");
+    assert_eq!(
+        platform::bundle_resources_dir(exe_dir),
+        Some(PathBuf::from(
+            "/Applications/My Game.app/Contents/Resources"
+        ))
+    );
+    assert_eq!(
+        platform::bundle_resources_dir(std::path::Path::new("/opt/game/bin")),
+        None
+    );
+
+    let root = InstallRoot::detect().unwrap();
+    assert!(root.resolve("assets/../secret").is_err());
+    assert_eq!(
+        root.resolve("assets/icon.png").unwrap(),
+        root.path().join("assets/icon.png")
+    );
+}
+
+#[test]
+fn test_long_generated_names() {
+    let level = DynamicLevel {
+        id: "x".repeat(300),
+    };
+    assert!(matches!(
+        level.resolve(),
+        Err(PathValidationError::ComponentTooLong(_, 255))
+    ));
+
+    let name = format!("{}.sav", "long name ".repeat(30));
+    let shortened = long_names::shorten(&name, 255);
+    assert!(shortened.len() <= 255);
+    assert!(shortened.starts_with("long name long name"));
+    assert!(shortened.ends_with(".sav"));
+    assert_ne!(shortened, long_names::shorten(&format!("{name}2"), 255));
+    assert_eq!(long_names::shorten("short.sav", 255), "short.sav");
+}
+
+#[test]
+fn test_strict_profile_screens_confusables() {
+    assert!(screen_confusables("Spielstand 1").is_ok());
+    assert!(screen_confusables("save\u{200B}").is_err());
+    assert!(screen_confusables("m\u{03BF}d").is_err()); // Greek 'ο'
+
+    let spoofed = DynamicLevel {
+        id: "evil\u{202E}gpj".into(),
+    };
+    assert!(spoofed.resolve().is_ok());
+    let (result, sub_path) = profile::with_profile(profile::ValidationProfile::Strict, || {
+        (
+            spoofed.resolve(),
+            io::resolve_in(&ConfigDir, "mods/p\u{0430}ck"),
+        )
+    });
+    assert!(matches!(
+        result,
+        Err(PathValidationError::ConfusableComponent(_))
+    ));
+    assert!(sub_path.is_err());
+    assert_eq!(
+        profile::validation_profile(),
+        profile::ValidationProfile::Standard
+    );
+}
+
+#[test]
+fn test_path_limits() {
+    let limits = PathLimits {
+        max_components: Some(3),
+        max_length: Some(32),
+    };
+    assert!(validate_limits(std::path::Path::new("levels/1/map.dat"), &limits).is_ok());
+    assert!(matches!(
+        validate_limits(std::path::Path::new("a/b/c/d"), &limits),
+        Err(PathValidationError::LimitExceeded {
+            limit: PathLimit::MaxComponents(3),
+            actual: 4
+        })
+    ));
+    assert!(matches!(
+        validate_limits(std::path::Path::new(&"x".repeat(40)), &limits),
+        Err(PathValidationError::LimitExceeded {
+            limit: PathLimit::MaxLength(32),
+            actual: 40
+        })
+    ));
+
+    // The marker directory counts towards the limits of sub paths.
+    let limits = PathLimits {
+        max_components: Some(4),
+        max_length: None,
+    };
+    let level = DynamicLevel { id: "1".into() };
+    profile::with_path_limits(limits, || {
+        assert!(level.resolve().is_ok());
+        assert!(io::resolve_in(&level, "a").is_ok());
+        assert!(matches!(
+            io::resolve_in(&level, "a/b"),
+            Err(PathIoError::Validation(
+                PathValidationError::LimitExceeded {
+                    limit: PathLimit::MaxComponents(4),
+                    actual: 5
+                }
+            ))
+        ));
+    });
+    assert!(io::resolve_in(&level, "a/b").is_ok());
+}
+
+#[test]
+fn test_path_interner() {
+    let mut paths = path_id::PathInterner::default();
+    let level = paths
+        .intern_marker(&DynamicLevel {
+            id: "forest".into(),
+        })
+        .unwrap();
+    let save = paths.intern_marker(&SavePath).unwrap();
+    assert_ne!(level, save);
+    assert_eq!(paths.intern(SavePath.resolve().unwrap()), save);
+    assert_eq!(paths.len(), 2);
+    assert!(paths.get(level).unwrap().ends_with("levels/forest/map.dat"));
+    assert_eq!(paths.lookup(SavePath.resolve().unwrap()), Some(save));
+    assert_eq!(std::mem::size_of::<path_id::PathId>(), 4);
+}
+
+#[derive(Reflect, Default)]
+struct ImportDir;
+
+impl TypedPath for ImportDir {
+    const TEMPLATE: &'static str = "tests/imports";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_import_external_file() {
+    assert_eq!(io::sanitize_file_name("my:avatar?.png"), "my_avatar_.png");
+    assert_eq!(io::sanitize_file_name("CON"), "_CON");
+    assert_eq!(io::sanitize_file_name("skin. "), "skin");
+    assert_eq!(io::sanitize_file_name(".."), "file");
+
+    let _ = std::fs::remove_dir_all(ImportDir.resolve().unwrap());
+    let source = std::env::temp_dir().join("bevy_paths avatar.png");
+    std::fs::write(&source, b"png").unwrap();
+    let first = io::import_external_file(&ImportDir, &source).unwrap();
+    let second = io::import_external_file(&ImportDir, &source).unwrap();
+    std::fs::remove_file(&source).unwrap();
+
+    assert_eq!(first, "bevy_paths avatar.png");
+    assert_eq!(second, "bevy_paths avatar (2).png");
+    assert_eq!(io::read(&ImportDir, &second).unwrap(), b"png");
+}
+
+#[derive(Path, Reflect, Default)]
+#[file(
+    "tests/platform/{slot}",
+    windows = "tests\\Platform\\{slot}",
+    macos = "tests/Platform/{slot}"
+)]
+struct PlatformSaves {
+    slot: u8,
+}
+
+#[test]
+fn test_platform_specific_templates() {
+    let expected = if cfg!(any(windows, target_os = "macos")) {
+        "tests/Platform/{slot}"
+    } else {
+        "tests/platform/{slot}"
+    };
+    assert_eq!(PlatformSaves::TEMPLATE, expected);
+    assert_eq!(PlatformSaves::PLACEHOLDERS, &["slot"]);
+    assert!(PlatformSaves { slot: 2 }.resolve().unwrap().ends_with("2"));
+}
+
+#[test]
+fn test_plugin_defers_initialization() {
+    let mut app = bevy_app::App::new();
+    app.add_plugins(PathsPlugin::default());
+    assert!(!app.world().contains_resource::<Confinement>());
+    app.update();
+    assert!(app.world().contains_resource::<Confinement>());
+    assert!(app.world().contains_resource::<InstallRoot>());
+}
+
+#[derive(Path, Reflect, Default)]
+#[file("tests/worlds/{save}")]
+struct SaveWorld {
+    save: String,
+}
+
+#[derive(Path, Reflect, Default)]
+#[file("{@SaveWorld}/chunks/{x}_{y}.bin")]
+struct ChunkFile {
+    save: String,
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn test_template_composition() {
+    assert_eq!(
+        ChunkFile::template(),
+        "tests/worlds/{save}/chunks/{x}_{y}.bin"
+    );
+    assert_eq!(&*ChunkFile::placeholders(), &["x", "y", "save"]);
+    let chunk = ChunkFile {
+        save: "alpha".into(),
+        x: 3,
+        y: -1,
+    };
+    let world = SaveWorld {
+        save: "alpha".into(),
+    };
+    assert_eq!(
+        chunk.resolve().unwrap(),
+        world.resolve().unwrap().join("chunks/3_-1.bin")
+    );
+}
+
+#[derive(Reflect, Default)]
+struct OverlayDir;
+
+impl TypedPath for OverlayDir {
+    const TEMPLATE: &'static str = "tests/overlay";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_overlay_read_through() {
+    let defaults = std::env::temp_dir().join("bevy_paths_overlay_defaults");
+    std::fs::create_dir_all(&defaults).unwrap();
+    std::fs::write(defaults.join("keys.ron"), b"(jump: \"Space\")").unwrap();
+    let _ = std::fs::remove_dir_all(OverlayDir.resolve().unwrap());
+    overlay::register_overlay::<OverlayDir>(&defaults);
+
+    assert_eq!(
+        io::read(&OverlayDir, "keys.ron").unwrap(),
+        b"(jump: \"Space\")"
+    );
+    assert!(!io::resolve_in(&OverlayDir, "keys.ron").unwrap().exists());
+    assert!(
+        io::read(&OverlayDir, "missing.ron")
+            .unwrap_err()
+            .is_not_found()
+    );
+
+    let path = overlay::materialize(&OverlayDir, "keys.ron").unwrap();
+    assert!(path.exists());
+    io::write(&OverlayDir, "keys.ron", b"(jump: \"W\")").unwrap();
+    assert_eq!(io::read(&OverlayDir, "keys.ron").unwrap(), b"(jump: \"W\")");
+    assert_eq!(
+        std::fs::read(defaults.join("keys.ron")).unwrap(),
+        b"(jump: \"Space\")"
+    );
+    std::fs::remove_dir_all(&defaults).unwrap();
+}
+
+#[test]
+fn test_consent_store() {
+    use consent::ConsentState;
+
+    let mut app = bevy_app::App::new();
+    app.add_plugins(PathsPlugin::default());
+    consent::set_consent("tests.analytics", ConsentState::Unknown).unwrap();
+    app.update();
+    app.world_mut()
+        .resource_mut::<bevy_ecs::message::Messages<consent::ConsentChanged>>()
+        .clear();
+
+    consent::set_consent("tests.analytics", ConsentState::Granted).unwrap();
+    assert_eq!(
+        consent::consent("tests.analytics").unwrap(),
+        ConsentState::Granted
+    );
+    assert!(
+        consent::consent_record("tests.analytics")
+            .unwrap()
+            .is_some()
+    );
+    consent::set_consent("tests.analytics", ConsentState::Granted).unwrap();
+
+    app.update();
+    let changes: Vec<_> = app
+        .world_mut()
+        .resource_mut::<bevy_ecs::message::Messages<consent::ConsentChanged>>()
+        .drain()
+        .filter(|change| change.key == "tests.analytics")
+        .collect();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].state, ConsentState::Granted);
+
+    // A failed write keeps the previous decision.
+    let blocker = io::tmp_path(&consent::consent_path().unwrap());
+    std::fs::create_dir_all(&blocker).unwrap();
+    let result = consent::set_consent("tests.analytics", ConsentState::Denied);
+    std::fs::remove_dir(&blocker).unwrap();
+    assert!(result.is_err());
+    assert_eq!(
+        consent::consent("tests.analytics").unwrap(),
+        ConsentState::Granted
+    );
+
+    consent::set_consent("tests.analytics", ConsentState::Unknown).unwrap();
+    assert_eq!(
+        consent::consent("tests.analytics").unwrap(),
+        ConsentState::Unknown
+    );
+}
+
+#[derive(Reflect, Default, Clone)]
+struct StatDir;
+
+impl TypedPath for StatDir {
+    const TEMPLATE: &'static str = "tests/stat";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_marker_stat() {
+    let _ = std::fs::remove_dir_all(StatDir.resolve().unwrap());
+    assert_eq!(stat::stat(&StatDir).unwrap().file_count, 0);
+    io::write(&StatDir, "a.sav", &[0; 100]).unwrap();
+    io::write(&StatDir, "slot/b.sav", &[0; 20]).unwrap();
+
+    let direct = stat::stat(&StatDir).unwrap();
+    assert_eq!((direct.size, direct.file_count), (120, 2));
+    assert!(direct.newest_modified.is_some());
+
+    let mut app = bevy_app::App::new();
+    app.add_plugins(PathsPlugin::default());
+    app.world_mut()
+        .resource_mut::<stat::MarkerStatCache>()
+        .refresh(&StatDir);
+    for _ in 0..1000 {
+        app.update();
+        if !app
+            .world()
+            .resource::<stat::MarkerStatCache>()
+            .is_refreshing::<StatDir>()
+        {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    let cache = app.world().resource::<stat::MarkerStatCache>();
+    assert_eq!(cache.get::<StatDir>().unwrap().unwrap().size, 120);
+}
+
+#[derive(Reflect, Default, Clone)]
+struct DebounceDir;
+
+impl TypedPath for DebounceDir {
+    const TEMPLATE: &'static str = "tests/debounce";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_debounced_save() {
+    let _ = std::fs::remove_file(io::resolve_in(&DebounceDir, "window.ron").unwrap());
+    let hour = std::time::Duration::from_secs(3600);
+    debounce::debounced_save(&DebounceDir, "window.ron", &Volume(1), hour).unwrap();
+    debounce::debounced_save(&DebounceDir, "window.ron", &Volume(2), hour).unwrap();
+    assert!(debounce::has_pending_saves());
+    assert!(
+        io::read(&DebounceDir, "window.ron")
+            .unwrap_err()
+            .is_not_found()
+    );
+
+    debounce::flush_debounced_saves().unwrap();
+    assert_eq!(
+        io::load_ron::<_, Volume>(&DebounceDir, "window.ron")
+            .unwrap()
+            .0,
+        2
+    );
+
+    // A failed save is retried, unless a newer one replaced it.
+    let blocker = io::tmp_path(&io::resolve_in(&DebounceDir, "window.ron").unwrap());
+    std::fs::create_dir_all(&blocker).unwrap();
+    debounce::debounced_save(&DebounceDir, "window.ron", &Volume(3), hour).unwrap();
+    assert!(debounce::flush_debounced_saves().is_err());
+    assert!(debounce::has_pending_saves());
+    std::fs::remove_dir(&blocker).unwrap();
+    debounce::flush_debounced_saves().unwrap();
+    assert_eq!(
+        io::load_ron::<_, Volume>(&DebounceDir, "window.ron")
+            .unwrap()
+            .0,
+        3
+    );
+}
+
+#[test]
+fn test_listing_order() {
+    use sort::SortOrder;
+
+    assert_eq!(
+        sort::natural_cmp("save9", "save10"),
+        std::cmp::Ordering::Less
+    );
+    assert_eq!(
+        sort::natural_cmp("save7", "save07"),
+        std::cmp::Ordering::Less
+    );
+
+    let names = |paths: &[PathBuf]| -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    };
+    let mut paths: Vec<PathBuf> = ["save10", "Élan", "save9", "emma"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    sort::sort_paths(&mut paths, SortOrder::Natural);
+    assert_eq!(names(&paths), ["emma", "save9", "save10", "Élan"]);
+    sort::sort_paths(&mut paths, SortOrder::Collated);
+    assert_eq!(names(&paths), ["Élan", "emma", "save9", "save10"]);
+
+    let _ = std::fs::remove_dir_all(ListDir.resolve().unwrap());
+    io::write(&ListDir, "slot_10.sav", b"").unwrap();
+    io::write(&ListDir, "slot_2.sav", b"").unwrap();
+    let listed = io::list(&ListDir, "", SortOrder::Natural).unwrap();
+    assert_eq!(names(&listed), ["slot_2.sav", "slot_10.sav"]);
+    assert!(
+        io::list(&ListDir, "missing", SortOrder::Name)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[derive(Reflect, Default)]
+struct ListDir;
+
+impl TypedPath for ListDir {
+    const TEMPLATE: &'static str = "tests/list";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_resolution_cache() {
+    let chunk = MultiVarPath { x: 4, y: 2 };
+    let uncached = resolve_cache::resolve_cached(&chunk).unwrap();
+    assert_eq!(&*uncached, chunk.resolve().unwrap());
+
+    resolve_cache::enable_resolution_cache::<MultiVarPath>(2);
+    let first = resolve_cache::resolve_cached(&chunk).unwrap();
+    let second = resolve_cache::resolve_cached(&chunk).unwrap();
+    assert!(std::sync::Arc::ptr_eq(&first, &second));
+    assert!(first.ends_with("chunks/4_2.dat"));
+
+    // Using an entry keeps it, the least recently used one is evicted.
+    let other = resolve_cache::resolve_cached(&MultiVarPath { x: 0, y: 0 }).unwrap();
+    resolve_cache::resolve_cached(&chunk).unwrap();
+    resolve_cache::resolve_cached(&MultiVarPath { x: 1, y: 1 }).unwrap();
+    let kept = resolve_cache::resolve_cached(&chunk).unwrap();
+    assert!(std::sync::Arc::ptr_eq(&first, &kept));
+    let evicted = resolve_cache::resolve_cached(&MultiVarPath { x: 0, y: 0 }).unwrap();
+    assert!(!std::sync::Arc::ptr_eq(&other, &evicted));
+    assert_eq!(other, evicted);
+
+    // Settings changes and scoped overrides bypass cached paths.
+    let scoped = profile::with_profile(profile::ValidationProfile::Strict, || {
+        resolve_cache::resolve_cached(&chunk).unwrap()
+    });
+    assert!(!std::sync::Arc::ptr_eq(&first, &scoped));
+    resolve_cache::invalidate();
+    let refreshed = resolve_cache::resolve_cached(&chunk).unwrap();
+    assert!(!std::sync::Arc::ptr_eq(&first, &refreshed));
+    assert_eq!(first, refreshed);
+    resolve_cache::disable_resolution_cache::<MultiVarPath>();
+}
+
+#[test]
+fn test_resolution_cache_compares_values_on_hash_collision() {
+    let mut cache = resolve_cache::LruCache::new(4);
+    let values = |x: &str| vec![Some(x.to_string())];
+    let path: std::sync::Arc<std::path::Path> = std::path::Path::new("chunks/1.dat").into();
+    cache.insert(7, values("1"), path.clone());
+    assert!(cache.get(7, || values("2")).is_none());
+    cache.insert(7, values("2"), std::path::Path::new("chunks/2.dat").into());
+    assert_eq!(cache.get(7, || values("1")), Some(path));
+    assert_eq!(
+        cache.get(7, || values("2")).as_deref(),
+        Some(std::path::Path::new("chunks/2.dat"))
+    );
+}
+
+#[derive(Reflect, Default)]
+struct UnclosedTemplate;
+
+impl TypedPath for UnclosedTemplate {
+    const TEMPLATE: &'static str = "levels/{id/map.dat";
+    const PLACEHOLDERS: &'static [&'static str] = &[];
+}
+
+#[test]
+fn test_template_syntax_errors() {
+    let position = |template| match parse_template(template) {
+        Err(PathValidationError::TemplateSyntax { position, .. }) => Some(position),
+        _ => None,
+    };
+    assert_eq!(position("saves/{slot"), Some(6));
+    assert_eq!(position("saves/{a{b}}"), Some(8));
+    assert_eq!(position("saves/{}"), Some(6));
+    assert_eq!(position("saves/slot}"), Some(10));
+    assert_eq!(position("saves/{sl-ot}"), Some(9));
+    assert_eq!(position("saves/{@world::WorldDir}/{x}"), None);
+
+    assert!(matches!(
+        UnclosedTemplate.resolve(),
+        Err(PathValidationError::TemplateSyntax { position: 7, .. })
+    ));
+    let level = DynamicLevel { id: "{id}".into() };
+    assert!(matches!(
+        level.resolve(),
+        Err(PathValidationError::InvalidComponent(_))
+    ));
+    assert!(matches!(
+        MissingField.resolve(),
+        Err(PathValidationError::MissingPlaceholder(name)) if name == "id"
+    ));
+}
+
+#[derive(Reflect, Default)]
+struct MissingField;
+
+impl TypedPath for MissingField {
+    const TEMPLATE: &'static str = "levels/{id}/map.dat";
+    const PLACEHOLDERS: &'static [&'static str] = &["id"];
+}
+
+#[test]
+fn test_substituted_values_are_validated() {
+    for id in ["a:<>*", "CON", "trailing.", "a{b"] {
+        let level = DynamicLevel { id: id.into() };
+        assert!(
+            matches!(
+                level.resolve(),
+                Err(PathValidationError::InvalidComponent(_))
+            ),
+            "{id}"
+        );
+    }
+    let level = DynamicLevel { id: "1".into() };
+    assert!(matches!(
+        io::resolve_in(&level, "slot:1.sav"),
+        Err(PathIoError::Validation(
+            PathValidationError::InvalidComponent(_)
+        ))
+    ));
+    assert!(io::resolve_in(&level, "{slot}.sav").is_err());
+    assert_eq!(io::sanitize_file_name("{slot}.sav"), "_slot_.sav");
+}
@@ -0,0 +1,168 @@
+//! Write-ahead-log style saves for incremental world state.
+//!
+//! A WAL save consists of two files inside a marker:
+//!
+//! - `<name>.base`: the last compacted full save, prefixed with its generation.
+//! - `<name>.wal`: a [`journal`] whose first record is the generation it belongs to,
+//!   followed by the deltas appended since.
+//!
+//! [`WalSave::compact`] atomically writes a new base with the next generation before
+//! truncating the journal. If the process crashes in between, the journal still carries
+//! the old generation and is discarded by [`recovery`](crate::recovery) on the next start, or
+//! at the latest by the next [`WalSave::open`], so deltas are never applied twice.
+
+use {
+    crate::{
+        TypedPath, external, io,
+        io::PathIoError,
+        journal::{self, Journal},
+        recovery::{self, Intent},
+    },
+    bevy_log::{info_span, trace_span},
+    std::{
+        any::type_name,
+        fs,
+        path::{Path, PathBuf},
+        time::Duration,
+    },
+};
+
+/// The content of a WAL save: the last compacted base and the deltas appended since.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalState {
+    /// The compacted base, `None` before the first compaction.
+    pub base: Option<Vec<u8>>,
+    /// The deltas in the order they were appended.
+    pub deltas: Vec<Vec<u8>>,
+}
+
+/// An open WAL save inside the marker `P`.
+pub struct WalSave<P: TypedPath> {
+    marker: P,
+    base_file: PathBuf,
+    wal_file: PathBuf,
+    generation: u64,
+    journal: Option<Journal<P>>,
+}
+
+impl<P: TypedPath> WalSave<P> {
+    /// Opens the save `name` inside `marker`, discarding a journal left over from an
+    /// interrupted compaction.
+    pub fn open(marker: P, name: impl AsRef<Path>) -> Result<Self, PathIoError> {
+        let name = name.as_ref();
+        let _span = info_span!("bevy_paths::wal", op = "open", marker = type_name::<P>()).entered();
+        let mut save = Self {
+            marker,
+            base_file: with_suffix(name, ".base"),
+            wal_file: with_suffix(name, ".wal"),
+            generation: 0,
+            journal: None,
+        };
+        save.generation = match io::read(&save.marker, &save.base_file) {
+            Ok(bytes) => split_generation(&bytes, &save.base_file)?.0,
+            Err(e) if e.is_not_found() => 0,
+            Err(e) => return Err(e),
+        };
+        let journal_generation = journal::replay(&save.marker, &save.wal_file)?
+            .next()
+            .transpose()?
+            .and_then(|header| header.try_into().ok().map(u64::from_le_bytes));
+        if journal_generation == Some(save.generation) {
+            save.journal = Some(save.open_journal()?);
+        } else {
+            save.reset_journal()?;
+        }
+        Ok(save)
+    }
+
+    /// Durably appends a delta.
+    pub fn append_delta(&mut self, delta: &[u8]) -> Result<(), PathIoError> {
+        let _span = trace_span!(
+            "bevy_paths::wal",
+            op = "append_delta",
+            marker = type_name::<P>(),
+            bytes = delta.len()
+        )
+        .entered();
+        self.journal
+            .as_mut()
+            .expect("the journal is open outside of compaction")
+            .append(delta)
+    }
+
+    /// Reads the base and all deltas appended since.
+    pub fn state(&self) -> Result<WalState, PathIoError> {
+        let base = match io::read(&self.marker, &self.base_file) {
+            Ok(bytes) => Some(split_generation(&bytes, &self.base_file)?.1.to_vec()),
+            Err(e) if e.is_not_found() => None,
+            Err(e) => return Err(e),
+        };
+        let deltas = journal::replay(&self.marker, &self.wal_file)?
+            .skip(1)
+            .collect::<Result<_, _>>()?;
+        Ok(WalState { base, deltas })
+    }
+
+    /// Atomically replaces the base with `base` and truncates the journal.
+    ///
+    /// `base` must contain all deltas appended so far.
+    pub fn compact(&mut self, base: &[u8]) -> Result<(), PathIoError> {
+        let _span = info_span!(
+            "bevy_paths::wal",
+            op = "compact",
+            marker = type_name::<P>(),
+            generation = self.generation + 1
+        )
+        .entered();
+        let generation = self.generation + 1;
+        let mut bytes = generation.to_le_bytes().to_vec();
+        bytes.extend_from_slice(base);
+        let intent = Intent::WalCompaction {
+            base: io::resolve_in(&self.marker, &self.base_file)?,
+            wal: io::resolve_in(&self.marker, &self.wal_file)?,
+            generation,
+        };
+        recovery::guarded(intent, || {
+            io::write(&self.marker, &self.base_file, &bytes)?;
+            self.generation = generation;
+            self.reset_journal()
+        })
+    }
+
+    fn open_journal(&self) -> Result<Journal<P>, PathIoError> {
+        Ok(Journal::open(&self.marker, &self.wal_file)?.with_sync_interval(Duration::ZERO))
+    }
+
+    /// Replaces the journal with an empty one for the current generation.
+    fn reset_journal(&mut self) -> Result<(), PathIoError> {
+        drop(self.journal.take());
+        let path = io::resolve_in(&self.marker, &self.wal_file)?;
+        match external::owned_write(&[&path], || fs::remove_file(&path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(PathIoError::Io(path, e));
+            }
+            _ => {}
+        }
+        let mut journal = self.open_journal()?;
+        journal.append(&self.generation.to_le_bytes())?;
+        self.journal = Some(journal);
+        Ok(())
+    }
+}
+
+/// `name` with `suffix` appended to its last component.
+fn with_suffix(name: &Path, suffix: &str) -> PathBuf {
+    let mut file = name.to_path_buf();
+    file.as_mut_os_string().push(suffix);
+    file
+}
+
+fn split_generation<'a>(bytes: &'a [u8], file: &Path) -> Result<(u64, &'a [u8]), PathIoError> {
+    match bytes.split_first_chunk::<8>() {
+        Some((generation, data)) => Ok((u64::from_le_bytes(*generation), data)),
+        None => Err(PathIoError::Format(
+            file.to_path_buf(),
+            "WAL base is missing its generation header.".to_string(),
+        )),
+    }
+}
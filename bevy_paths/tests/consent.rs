@@ -0,0 +1,33 @@
+//! Consent stored in a config marker. It lives in its own test binary, as the location of the
+//! consent file applies to the whole process.
+
+use bevy_paths::{
+    consent::{self, ConsentState},
+    prelude::*,
+};
+use bevy_reflect::Reflect;
+
+#[derive(Path, Reflect, Default)]
+#[file("tests/consent_config")]
+struct ConfigDir;
+
+#[test]
+fn test_consent_in_config_marker() {
+    let mut app = bevy_app::App::new();
+    app.add_plugins(PathsPlugin::default().with_consent_marker::<ConfigDir>());
+    let path = ConfigDir
+        .resolve()
+        .unwrap()
+        .join(consent::CONSENT_FILE_NAME);
+    assert_eq!(consent::consent_path().unwrap(), path);
+
+    consent::set_consent("tests.crash_reports", ConsentState::Granted).unwrap();
+    assert!(path.is_file());
+    assert!(
+        std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("tests.crash_reports")
+    );
+
+    consent::set_consent("tests.crash_reports", ConsentState::Unknown).unwrap();
+}
@@ -0,0 +1,100 @@
+//! IO statistics of the file helpers. They live in their own test binary, as the counters
+//! are collected by whichever app updates first.
+
+use bevy_paths::{
+    download::StagedDownload,
+    handle_pool::FileHandlePool,
+    io,
+    io_stats::{self, IoCounters, IoStats},
+    journal::Journal,
+    prelude::*,
+    progress::ProgressTracker,
+    sort::SortOrder,
+};
+use bevy_reflect::Reflect;
+
+#[derive(Path, Reflect, Default)]
+#[file("tests/io_stats/plain")]
+struct PlainDir;
+
+#[derive(Path, Reflect, Default)]
+#[file("tests/io_stats/source")]
+struct SourceDir;
+
+#[derive(Path, Reflect, Default)]
+#[file("tests/io_stats/copy")]
+struct CopyDir;
+
+#[derive(Path, Reflect, Default)]
+#[file("tests/io_stats/append")]
+struct AppendDir;
+
+#[test]
+fn test_io_stats_of_all_helpers() {
+    let mut app = bevy_app::App::new();
+    app.add_plugins(PathsPlugin::default());
+    for dir in [
+        PlainDir.resolve().unwrap(),
+        SourceDir.resolve().unwrap(),
+        CopyDir.resolve().unwrap(),
+        AppendDir.resolve().unwrap(),
+    ] {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    io_stats::set_io_stats_enabled(true);
+
+    io::write(&PlainDir, "a.bin", &[1; 10]).unwrap();
+    io::read(&PlainDir, "a.bin").unwrap();
+    io::read(&PlainDir, "a.bin").unwrap();
+
+    io::write(&SourceDir, "a.bin", &[1; 5]).unwrap();
+    assert_eq!(io::list(&SourceDir, "", SortOrder::Name).unwrap().len(), 1);
+    let mut tracker = ProgressTracker::default();
+    io::copy_marker(&SourceDir, &CopyDir, &tracker.start()).unwrap();
+
+    let mut journal = Journal::open(&AppendDir, "log.journal").unwrap();
+    journal.append(b"abc").unwrap();
+    drop(journal);
+    let mut pool = FileHandlePool::default();
+    pool.append(&AppendDir, "log.txt", b"abcd").unwrap();
+    pool.flush().unwrap();
+    let mut download = StagedDownload::open(AppendDir, "pack").unwrap();
+    download.write(&[0; 6]).unwrap();
+    app.update();
+
+    let stats = app.world().resource::<IoStats>();
+    assert_eq!(
+        stats.get::<PlainDir>(),
+        IoCounters {
+            reads: 2,
+            bytes_read: 20,
+            writes: 1,
+            bytes_written: 10,
+        }
+    );
+    assert_eq!(
+        stats.get::<SourceDir>(),
+        IoCounters {
+            reads: 2,
+            bytes_read: 5,
+            writes: 1,
+            bytes_written: 5,
+        }
+    );
+    assert_eq!(
+        stats.get::<CopyDir>(),
+        IoCounters {
+            writes: 1,
+            bytes_written: 5,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        stats.get::<AppendDir>(),
+        IoCounters {
+            writes: 3,
+            bytes_written: 13,
+            ..Default::default()
+        }
+    );
+}
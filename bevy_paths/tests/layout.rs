@@ -0,0 +1,24 @@
+//! Golden layout test. It lives in its own test binary, as [`layout::startup_app`] redirects
+//! the base path of the whole process.
+
+use bevy_paths::{io, layout, prelude::*};
+use bevy_reflect::Reflect;
+
+#[derive(Path, Reflect, Default)]
+#[file("tests/layout")]
+struct LayoutDir;
+
+#[test]
+fn test_golden_layout() {
+    layout::startup_app(|_| {});
+    let temp_dir = std::env::temp_dir().canonicalize().unwrap();
+    assert!(LayoutDir.resolve().unwrap().starts_with(temp_dir));
+
+    io::write(&LayoutDir, "config/video.ron", b"()").unwrap();
+    io::write(&LayoutDir, "saves/slot_1/world/meta.ron", b"()").unwrap();
+
+    layout::assert_layout(&LayoutDir, &["config/*.ron", "saves/**/meta.ron"]);
+    let mismatch = layout::compare_layout(&LayoutDir, &["config/*.ron", "logs/?.log"]).unwrap();
+    assert_eq!(mismatch.missing, ["logs/?.log"]);
+    assert_eq!(mismatch.unexpected, ["saves/slot_1/world/meta.ron"]);
+}
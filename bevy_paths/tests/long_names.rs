@@ -0,0 +1,47 @@
+//! Hashing of long names. It lives in its own test binary, as it changes the process-wide
+//! long name settings.
+
+use bevy_paths::{
+    long_names::{self, LongNamePolicy},
+    prelude::*,
+};
+use bevy_reflect::Reflect;
+
+#[derive(Path, Reflect)]
+#[file("tests/long_names/{name}")]
+struct LongName {
+    name: String,
+}
+
+fn resolved_name(name: &str) -> String {
+    let path = LongName { name: name.into() }.resolve().unwrap();
+    path.file_name().unwrap().to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_hashed_long_names() {
+    long_names::set_long_name_policy(LongNamePolicy::Hash);
+
+    let name = format!("{}.sav", "x".repeat(300));
+    let shortened = resolved_name(&name);
+    assert!(shortened.len() <= long_names::MAX_COMPONENT_BYTES);
+    assert!(shortened.ends_with(".sav"));
+    assert_eq!(shortened, long_names::shorten(&name, 255));
+    assert_eq!(long_names::original_name(&shortened).unwrap(), Some(name));
+    assert_eq!(long_names::original_name("unknown.sav").unwrap(), None);
+    assert_eq!(resolved_name("short.sav"), "short.sav");
+
+    // Limits below the hash suffix are raised to it.
+    long_names::set_max_component_bytes(4);
+    assert_eq!(
+        long_names::max_component_bytes(),
+        long_names::MIN_COMPONENT_BYTES
+    );
+    let shortened = resolved_name("a_rather_long_name.sav");
+    assert_eq!(shortened.len(), long_names::MIN_COMPONENT_BYTES);
+    assert_eq!(shortened, long_names::shorten("a_rather_long_name.sav", 4));
+    assert_eq!(
+        long_names::original_name(&shortened).unwrap().as_deref(),
+        Some("a_rather_long_name.sav")
+    );
+}
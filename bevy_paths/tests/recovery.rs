@@ -0,0 +1,59 @@
+//! Startup recovery of interrupted operations. It lives in its own test binary, as recovery
+//! processes the intent journal below the base path of the whole process.
+
+use bevy_ecs::message::Messages;
+use bevy_paths::{PathsReady, io, journal::Journal, prelude::*};
+use bevy_reflect::Reflect;
+
+#[derive(Path, Reflect, Default)]
+#[file(".bevy_paths")]
+struct StateDir;
+
+#[derive(Path, Reflect, Default)]
+#[file("data")]
+struct DataDir;
+
+#[test]
+fn test_recovery_before_paths_ready() {
+    let root = std::env::temp_dir().join(format!("bevy_paths_recovery_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let mut app = bevy_app::App::new();
+    app.add_plugins(PathsPlugin::default().with_base_path(&root));
+
+    // A directory save interrupted after the old slot was moved to the backup.
+    io::write(&DataDir, "slot.bak/meta.ron", b"old").unwrap();
+    io::write(&DataDir, "slot.tmp/meta.ron", b"new").unwrap();
+    // A verified download interrupted before it was moved into place.
+    io::write(&DataDir, "pack.part", b"pack").unwrap();
+    let mut journal = Journal::open(&StateDir, "intents.journal").unwrap();
+    for record in [
+        r#"Begin(DirSwap(current: "data/slot", staging: "data/slot.tmp", backup: "data/slot.bak"))"#,
+        r#"Begin(DownloadFinalize(partial: "data/pack.part", target: "data/packs/pack.bin"))"#,
+        r#"Begin(DownloadFinalize(partial: "data/done.part", target: "data/done.bin"))"#,
+        r#"Done(DownloadFinalize(partial: "data/done.part", target: "data/done.bin"))"#,
+    ] {
+        journal.append(record.as_bytes()).unwrap();
+    }
+    drop(journal);
+
+    app.update();
+    let ready: Vec<_> = app
+        .world_mut()
+        .resource_mut::<Messages<PathsReady>>()
+        .drain()
+        .collect();
+    assert_eq!(ready, [PathsReady { recovered: 2 }]);
+
+    assert_eq!(io::read(&DataDir, "slot/meta.ron").unwrap(), b"new");
+    assert!(!io::resolve_in(&DataDir, "slot.bak").unwrap().exists());
+    assert!(!io::resolve_in(&DataDir, "slot.tmp").unwrap().exists());
+    assert_eq!(io::read(&DataDir, "packs/pack.bin").unwrap(), b"pack");
+    assert!(!io::resolve_in(&DataDir, "pack.part").unwrap().exists());
+    assert_eq!(
+        std::fs::metadata(root.join(bevy_paths::recovery::INTENT_JOURNAL_FILE))
+            .unwrap()
+            .len(),
+        0
+    );
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -0,0 +1,59 @@
+//! Moving the base path. It lives in its own test binary, as the base path applies to the whole
+//! process.
+
+use bevy_paths::{io, prelude::*, progress::ProgressTracker, relocate, sync_state};
+use bevy_reflect::Reflect;
+
+#[derive(Path, Reflect, Default)]
+#[file("saves")]
+struct SaveDir;
+
+#[test]
+fn test_move_project_root() {
+    let root = std::env::temp_dir().join(format!("bevy_paths_relocate_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let (old_base, new_base) = (root.join("old"), root.join("new"));
+    let mut app = bevy_app::App::new();
+    app.add_plugins(PathsPlugin::default().with_base_path(&old_base));
+    sync_state::track_sync_state::<SaveDir>();
+    io::write(&SaveDir, "slot_1/meta.ron", b"()").unwrap();
+    let state_len = std::fs::metadata(old_base.join(sync_state::SYNC_JOURNAL_FILE))
+        .unwrap()
+        .len();
+
+    let mut tracker = ProgressTracker::default();
+    let overlapping = relocate::move_project_root(old_base.join("inner"), &tracker.start());
+    assert!(matches!(
+        overlapping,
+        Err(PathIoError::RelocationRejected(..))
+    ));
+    assert!(!old_base.join("inner").exists());
+
+    let handle = tracker.start();
+    let moved = relocate::move_project_root(&new_base, &handle).unwrap();
+    assert_eq!(moved, new_base.canonicalize().unwrap());
+    let progress = handle.progress();
+    assert!(progress.finished);
+    assert_eq!(progress.bytes_done, 2 + state_len);
+
+    assert!(SaveDir.resolve().unwrap().starts_with(&moved));
+    assert_eq!(io::read(&SaveDir, "slot_1/meta.ron").unwrap(), b"()");
+    assert!(!old_base.join("saves").exists());
+    assert!(old_base.join(relocate::REDIRECT_FILE).is_file());
+
+    // Modified files are tracked relative to the base path, so they survive the move.
+    assert_eq!(
+        sync_state::modified_files(&SaveDir).unwrap(),
+        vec!["slot_1/meta.ron".to_string()]
+    );
+    let state = std::fs::read(moved.join(sync_state::SYNC_JOURNAL_FILE)).unwrap();
+    let root_text = root.to_string_lossy();
+    assert!(!String::from_utf8_lossy(&state).contains(&*root_text));
+
+    let rejected = relocate::move_project_root(&old_base, &tracker.start());
+    assert!(matches!(rejected, Err(PathIoError::RelocationRejected(..))));
+    let nested = relocate::move_project_root(moved.join("a/b"), &tracker.start());
+    assert!(matches!(nested, Err(PathIoError::RelocationRejected(..))));
+    assert!(!moved.join("a").exists());
+    let _ = std::fs::remove_dir_all(&root);
+}
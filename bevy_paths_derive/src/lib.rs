@@ -50,13 +50,51 @@
 //! # Attributes
 //!
 //! - `#[file("...")]`: Specifies the path template for the struct. Must be a **relative path** with optional `{placeholder}` fields.
+//!   Backslashes are normalized to `/`; add `separators = "reject"` to reject them instead,
+//!   including in field values and sub paths at runtime.
+//!   Platform-specific templates are given as `windows = "..."`, `macos = "..."` or `linux = "..."`,
+//!   e.g. `#[file("saves", windows = "Saves")]` for layouts that differ across platforms.
+//!   `{@Marker}` references another marker's template, e.g. `#[file("{@WorldDir}/chunks/{x}_{y}.bin")]`;
+//!   the referenced placeholders must be fields of the struct, and references must not form a cycle.
 //!
 //! # Errors
 //!
 //! The macro will generate a **compile error** if:
 //! - The `#[file("...")]` attribute is missing.
 //! - The path template is invalid (e.g., absolute paths, `..`, or invalid characters).
-//! - Placeholders do not match struct fields.
+//! - Placeholders do not match struct fields, including those of referenced markers.
+//! - References form a cycle.
+//!
+//! ```rust,compile_fail
+//! use bevy_paths::prelude::*;
+//! use bevy_reflect::Reflect;
+//!
+//! #[derive(Path, Reflect)]
+//! #[file("worlds/{save}")]
+//! struct WorldDir {
+//!     save: String,
+//! }
+//!
+//! // `save` is missing.
+//! #[derive(Path, Reflect)]
+//! #[file("{@WorldDir}/chunks/{x}.bin")]
+//! struct Chunk {
+//!     x: i32,
+//! }
+//! ```
+//!
+//! ```rust,compile_fail
+//! use bevy_paths::prelude::*;
+//! use bevy_reflect::Reflect;
+//!
+//! #[derive(Path, Reflect)]
+//! #[file("{@B}/a")]
+//! struct A;
+//!
+//! #[derive(Path, Reflect)]
+//! #[file("{@A}/b")]
+//! struct B;
+//! ```
 //!
 //! # Safety
 //!
@@ -65,7 +103,7 @@
 //! # Performance
 //!
 //! - Template validation is performed at compile time.
-//! - Placeholder extraction uses a regex (executed once at compile time).
+//! - Templates are parsed once at compile time; malformed braces are reported with their position.
 //!
 //! # Dependencies
 //!
@@ -76,10 +114,17 @@
 //! MIT
 
 use {
-    bevy_paths_validation::validate_structural_path,
+    bevy_paths_validation::{
+        SeparatorPolicy, TemplateSegment, normalize_separators, parse_template,
+        validate_structural_path_with,
+    },
     proc_macro::TokenStream,
     quote::quote,
-    syn::{DeriveInput, parse_macro_input},
+    syn::{
+        DeriveInput, Ident, LitStr, Token,
+        parse::{Parse, ParseStream},
+        parse_macro_input,
+    },
 };
 
 /// Derives the `TypedPath` trait for a struct.
@@ -89,6 +134,17 @@ use {
 /// - Validates the template using `bevy_paths_validation`.
 /// - Generates an implementation of `TypedPath` with the template and placeholders.
 ///
+/// A `{@Marker}` field expands to the template of another `TypedPath` type at resolution time;
+/// its placeholders are filled from the fields of this struct.
+///
+/// # Options
+///
+/// Options follow the template as `key = "value"` pairs:
+/// - `separators = "normalize" | "reject"`: Whether `\` is treated as `/` (default) or rejected,
+///   in the template as well as in field values and sub paths.
+/// - `windows = "..."`, `macos = "..."`, `linux = "..."`: Template used on that platform instead
+///   of the default one. Validated like the default and must use the same placeholders.
+///
 /// # Panics
 ///
 /// This macro will panic if:
@@ -97,36 +153,275 @@ use {
 #[proc_macro_derive(Path, attributes(file))]
 pub fn derive_path(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let template = extract_file_attribute(&input).expect("Missing #[file(\"...\")] attribute");
-    if let Err(e) = validate_structural_path(&template) {
-        let error_msg = format!("Invalid path template: {}", e);
-        return quote! { compile_error!(#error_msg); }.into();
-    }
+    let attribute =
+        match extract_file_attribute(&input).expect("Missing #[file(\"...\")] attribute") {
+            Ok(attribute) => attribute,
+            Err(e) => return e.to_compile_error().into(),
+        };
+    let policy = match attribute.option("separators") {
+        None => SeparatorPolicy::Normalize,
+        Some(value) if value.value() == "normalize" => SeparatorPolicy::Normalize,
+        Some(value) if value.value() == "reject" => SeparatorPolicy::Reject,
+        Some(value) => {
+            return syn::Error::new_spanned(value, "Expected \"normalize\" or \"reject\"")
+                .to_compile_error()
+                .into();
+        }
+    };
+    let template = match validated_template(&attribute.template, policy) {
+        Ok(template) => template,
+        Err(e) => return e.to_compile_error().into(),
+    };
     // Platzhalter extrahieren
-    let placeholders = extract_placeholders(&template);
+    let (placeholders, references) = match split_references(&attribute.template, &template) {
+        Ok(split) => split,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let mut template_expr = quote! { #template };
+    for (option, target_os) in FileAttribute::PLATFORMS.iter().rev() {
+        let Some(value) = attribute.option(option) else {
+            continue;
+        };
+        let platform_template = match validated_template(value, policy) {
+            Ok(template) => template,
+            Err(e) => return e.to_compile_error().into(),
+        };
+        let mut expected = extract_placeholders(&template);
+        let mut actual = extract_placeholders(&platform_template);
+        expected.sort();
+        actual.sort();
+        if expected != actual {
+            return syn::Error::new_spanned(
+                value,
+                "Platform templates must use the same placeholders as the default template",
+            )
+            .to_compile_error()
+            .into();
+        }
+        template_expr = quote! {
+            if cfg!(target_os = #target_os) { #platform_template } else { #template_expr }
+        };
+    }
+    let reject_backslashes = (policy == SeparatorPolicy::Reject)
+        .then(|| quote! { const REJECT_BACKSLASHES: bool = true; });
     let struct_name = &input.ident;
+    let fields = field_names(&input);
+    if let Some(missing) = placeholders.iter().find(|name| !fields.contains(name)) {
+        return syn::Error::new_spanned(
+            &attribute.template,
+            format!("Placeholder '{{{missing}}}' is not a field of `{struct_name}`"),
+        )
+        .to_compile_error()
+        .into();
+    }
+    if let Some((name, _)) = references
+        .iter()
+        .find(|(_, marker)| marker.is_ident(struct_name) || marker.is_ident("Self"))
+    {
+        return syn::Error::new_spanned(
+            &attribute.template,
+            format!("'{{@{name}}}' references the marker itself"),
+        )
+        .to_compile_error()
+        .into();
+    }
+    let composition = (!references.is_empty()).then(|| {
+        let names = references.iter().map(|(name, _)| format!("{{@{name}}}"));
+        let markers: Vec<_> = references.iter().map(|(_, marker)| marker).collect();
+        let own = placeholders.len();
+        quote! {
+            // Evaluated at compile time, so a reference cycle is a const evaluation cycle.
+            const COMPOSED_PLACEHOLDERS: &'static [&'static str] = {
+                const LEN: usize =
+                    #own #(+ <#markers as TypedPath>::COMPOSED_PLACEHOLDERS.len())*;
+                const ALL: [&str; LEN] = {
+                    let own: [&str; #own] = [#(#placeholders),*];
+                    let mut all = [""; LEN];
+                    let mut i = 0;
+                    while i < own.len() {
+                        all[i] = own[i];
+                        i += 1;
+                    }
+                    #(
+                        let referenced = <#markers as TypedPath>::COMPOSED_PLACEHOLDERS;
+                        let mut j = 0;
+                        while j < referenced.len() {
+                            all[i] = referenced[j];
+                            i += 1;
+                            j += 1;
+                        }
+                    )*
+                    all
+                };
+                &ALL
+            };
+
+            fn template() -> ::std::borrow::Cow<'static, str> {
+                let mut template = Self::TEMPLATE.to_string();
+                #(template = template.replace(#names, &<#markers as TypedPath>::template());)*
+                ::std::borrow::Cow::Owned(template)
+            }
+        }
+    });
+    let reference_checks = (!references.is_empty()).then(|| {
+        let checks = references.iter().map(|(name, marker)| {
+            let message =
+                format!("`@{name}` uses placeholders that are not fields of `{struct_name}`");
+            quote! {
+                let required = <#marker as TypedPath>::COMPOSED_PLACEHOLDERS;
+                let mut r = 0;
+                while r < required.len() {
+                    assert!(is_field(required[r]), #message);
+                    r += 1;
+                }
+            }
+        });
+        quote! {
+            const _: () = {
+                const fn is_field(name: &str) -> bool {
+                    let fields: &[&str] = &[#(#fields),*];
+                    let mut f = 0;
+                    while f < fields.len() {
+                        let (a, b) = (fields[f].as_bytes(), name.as_bytes());
+                        let mut equal = a.len() == b.len();
+                        let mut i = 0;
+                        while equal && i < a.len() {
+                            equal = a[i] == b[i];
+                            i += 1;
+                        }
+                        if equal {
+                            return true;
+                        }
+                        f += 1;
+                    }
+                    false
+                }
+                #(#checks)*
+            };
+        }
+    });
     quote! {
         impl TypedPath for #struct_name {
-            const TEMPLATE: &'static str = #template;
+            const TEMPLATE: &'static str = #template_expr;
             const PLACEHOLDERS: &'static [&'static str] = &[#(#placeholders),*];
+            #reject_backslashes
+            #composition
         }
+
+        #reference_checks
     }
     .into()
 }
 
-fn extract_file_attribute(input: &DeriveInput) -> Option<String> {
+/// The names of the named fields of `input`.
+fn field_names(input: &DeriveInput) -> Vec<String> {
+    match &input.data {
+        syn::Data::Struct(data) => data
+            .fields
+            .iter()
+            .filter_map(|field| field.ident.as_ref().map(ToString::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Splits the `{...}` fields of `template` into placeholders and `{@Marker}` references.
+fn split_references(
+    literal: &LitStr,
+    template: &str,
+) -> syn::Result<(Vec<String>, Vec<(String, syn::Path)>)> {
+    let mut placeholders = Vec::new();
+    let mut references = Vec::new();
+    for field in extract_placeholders(template) {
+        match field.strip_prefix('@') {
+            Some(name) => {
+                let marker = syn::parse_str(name).map_err(|_| {
+                    syn::Error::new_spanned(
+                        literal,
+                        format!("Invalid marker reference '{{{field}}}'"),
+                    )
+                })?;
+                references.push((name.to_string(), marker));
+            }
+            None => placeholders.push(field),
+        }
+    }
+    Ok((placeholders, references))
+}
+
+/// Validates a template literal and returns it with normalized separators.
+fn validated_template(literal: &LitStr, policy: SeparatorPolicy) -> syn::Result<String> {
+    let raw_template = literal.value();
+    let error = |e| syn::Error::new_spanned(literal, format!("Invalid path template: {}", e));
+    parse_template(&raw_template).map_err(error)?;
+    validate_structural_path_with(&raw_template, policy).map_err(error)?;
+    Ok(normalize_separators(&raw_template).into_owned())
+}
+
+/// The parsed `#[file("template", key = "value", ...)]` attribute.
+struct FileAttribute {
+    template: LitStr,
+    options: Vec<(Ident, LitStr)>,
+}
+
+impl FileAttribute {
+    const OPTIONS: &[&str] = &["separators", "windows", "macos", "linux"];
+
+    /// Platform override options and the `target_os` they apply to.
+    const PLATFORMS: &[(&str, &str)] = &[
+        ("windows", "windows"),
+        ("macos", "macos"),
+        ("linux", "linux"),
+    ];
+
+    fn option(&self, name: &str) -> Option<&LitStr> {
+        self.options
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+impl Parse for FileAttribute {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let template = input.parse()?;
+        let mut options = Vec::new();
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if !Self::OPTIONS.iter().any(|option| key == option) {
+                let expected = Self::OPTIONS.join("`, `");
+                return Err(syn::Error::new_spanned(
+                    &key,
+                    format!("Unknown option, expected one of `{expected}`"),
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            options.push((key, input.parse()?));
+        }
+        if !input.is_empty() {
+            return Err(input.error("Expected `,`"));
+        }
+        Ok(Self { template, options })
+    }
+}
+
+fn extract_file_attribute(input: &DeriveInput) -> Option<syn::Result<FileAttribute>> {
     input
         .attrs
         .iter()
-        .find(|attr| attr.path().is_ident("file"))?
-        .parse_args::<syn::LitStr>()
-        .ok()
-        .map(|lit| lit.value())
+        .find(|attr| attr.path().is_ident("file"))
+        .map(|attr| attr.parse_args::<FileAttribute>())
 }
 
+/// The placeholder names of an already validated template, with `@` marking references.
 fn extract_placeholders(template: &str) -> Vec<String> {
-    let re = regex::Regex::new(r"\{([^}]+)\}").unwrap();
-    re.captures_iter(template)
-        .map(|cap| cap[1].to_string())
+    parse_template(template)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|segment| match segment {
+            TemplateSegment::Literal(_) => None,
+            TemplateSegment::Placeholder(name) => Some(name.to_string()),
+            TemplateSegment::Reference(name) => Some(format!("@{name}")),
+        })
         .collect()
 }
@@ -1,7 +1,8 @@
 #![warn(missing_docs)]
 
+use std::borrow::Cow;
 use std::io;
-use std::path::{Component, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use unicode_normalization::UnicodeNormalization;
 
 /// The `bevy_paths_validation` crate provides **cross-platform path validation** for the `bevy_paths` ecosystem.
@@ -74,6 +75,20 @@ pub enum PathValidationError {
     #[error("Registered path must be relative, but an absolute path was provided.")]
     AbsolutePathNotAllowed,
 
+    /// The path contains a backslash while [`SeparatorPolicy::Reject`] is in effect.
+    ///
+    /// # Recovery
+    /// Use `/` as the separator.
+    #[error("Registered path cannot contain '\\'. Use '/' as the separator.")]
+    BackslashNotAllowed,
+
+    /// The path is not valid UTF-8.
+    ///
+    /// # Recovery
+    /// Use UTF-8 file and directory names only.
+    #[error("Registered path '{0}' is not valid UTF-8.")]
+    NonUtf8Path(PathBuf),
+
     /// The path contains `.` or `..` components.
     ///
     /// # Recovery
@@ -84,10 +99,57 @@ pub enum PathValidationError {
     /// A path component contains invalid characters or is a reserved name.
     ///
     /// # Recovery
-    /// Remove invalid characters (`<`, `>`, `:`, `|`, `?`, `*`) or rename reserved components.
+    /// Remove invalid characters (`<`, `>`, `:`, `|`, `?`, `*`, and `{`, `}` in resolved
+    /// paths) or rename reserved components.
     #[error("Path component '{0}' contains invalid characters or is a reserved name on Windows.")]
     InvalidComponent(String),
 
+    /// A placeholder of the template has no value.
+    ///
+    /// # Recovery
+    /// Add a field with the placeholder's name to the marker, or remove the placeholder.
+    #[error("Placeholder '{{{0}}}' has no value.")]
+    MissingPlaceholder(String),
+
+    /// The template has malformed placeholder syntax.
+    ///
+    /// # Recovery
+    /// Close every `{` with `}`, do not nest braces and give every placeholder a name.
+    #[error("Invalid template syntax at byte {position}: {reason}.")]
+    TemplateSyntax {
+        /// The byte offset of the error in the template.
+        position: usize,
+        /// What is wrong.
+        reason: &'static str,
+    },
+
+    /// A path component contains zero-width or bidi control characters, or mixes scripts
+    /// in a way that can spoof another name.
+    ///
+    /// # Recovery
+    /// Remove invisible characters and use a single script per component.
+    #[error("Path component '{0}' contains invisible, bidi control or mixed-script characters.")]
+    ConfusableComponent(String),
+
+    /// The path exceeds one of the configured [`PathLimits`].
+    ///
+    /// # Recovery
+    /// Check the placeholder values that produced the path, or raise the limit.
+    #[error("Registered path exceeds the limit of {limit} with {actual}.")]
+    LimitExceeded {
+        /// The limit that was hit.
+        limit: PathLimit,
+        /// The value of the rejected path.
+        actual: usize,
+    },
+
+    /// A path component is longer than the file system allows.
+    ///
+    /// # Recovery
+    /// Use shorter placeholder values, or shorten long names with a hash suffix.
+    #[error("Path component '{0}' exceeds the limit of {1} bytes.")]
+    ComponentTooLong(String, usize),
+
     /// The base path exists but is not a directory.
     ///
     /// # Recovery
@@ -109,6 +171,13 @@ pub enum PathValidationError {
     #[error("The base path resolved to the file system root '{0}', which is disallowed.")]
     BasePathIsRoot(PathBuf),
 
+    /// The base path lies inside a protected system directory (e.g. `C:\Windows`, `/usr`).
+    ///
+    /// # Recovery
+    /// Use a per-user location or a directory the game owns.
+    #[error("The base path '{0}' lies inside a protected system directory.")]
+    BasePathInProtectedLocation(PathBuf),
+
     /// Failed to create the base path directory.
     ///
     /// # Recovery
@@ -158,7 +227,77 @@ pub enum PathValidationError {
 /// - Uses `std::path::Component` for traversal (fast and safe).
 /// - Applies Unicode normalization (`NFC`) to components.
 pub fn validate_structural_path(relative_path: &str) -> Result<PathBuf, PathValidationError> {
-    let s = relative_path.trim();
+    validate_structural_path_with(relative_path, SeparatorPolicy::Normalize)
+}
+
+/// Validates a **relative path template** like [`validate_structural_path`], handling
+/// backslashes according to `policy`.
+///
+/// # Examples
+///
+/// ```rust
+/// use bevy_paths_validation::{validate_structural_path_with, PathValidationError, SeparatorPolicy};
+/// use std::path::PathBuf;
+///
+/// assert_eq!(
+///     validate_structural_path_with("saves\\slots", SeparatorPolicy::Normalize).unwrap(),
+///     PathBuf::from("saves/slots")
+/// );
+/// assert!(matches!(
+///     validate_structural_path_with("saves\\slots", SeparatorPolicy::Reject),
+///     Err(PathValidationError::BackslashNotAllowed)
+/// ));
+/// ```
+pub fn validate_structural_path_with(
+    relative_path: &str,
+    policy: SeparatorPolicy,
+) -> Result<PathBuf, PathValidationError> {
+    validate_path(relative_path, policy, true)
+}
+
+/// Validates a **resolved relative path**, after placeholders were substituted.
+///
+/// Unlike [`validate_structural_path_with`], every component is checked, including those
+/// built from placeholder values. `{` and `}` are rejected, as they mark a placeholder that
+/// was not substituted. Non-UTF-8 input is rejected with [`PathValidationError::NonUtf8Path`].
+///
+/// # Examples
+///
+/// ```rust
+/// use bevy_paths_validation::{validate_resolved_path_with, PathValidationError, SeparatorPolicy};
+///
+/// assert!(validate_resolved_path_with("levels/1/map.dat", SeparatorPolicy::Normalize).is_ok());
+/// assert!(matches!(
+///     validate_resolved_path_with("levels/a:<>*/map.dat", SeparatorPolicy::Normalize),
+///     Err(PathValidationError::InvalidComponent(_))
+/// ));
+/// assert!(matches!(
+///     validate_resolved_path_with("levels/{id}/map.dat", SeparatorPolicy::Normalize),
+///     Err(PathValidationError::InvalidComponent(_))
+/// ));
+/// ```
+pub fn validate_resolved_path_with(
+    relative_path: impl AsRef<Path>,
+    policy: SeparatorPolicy,
+) -> Result<PathBuf, PathValidationError> {
+    let path = relative_path.as_ref();
+    let s = path
+        .to_str()
+        .ok_or_else(|| PathValidationError::NonUtf8Path(path.to_path_buf()))?;
+    validate_path(s, policy, false)
+}
+
+/// Validates `relative_path`. Components of templates containing placeholders are skipped.
+fn validate_path(
+    relative_path: &str,
+    policy: SeparatorPolicy,
+    template: bool,
+) -> Result<PathBuf, PathValidationError> {
+    if policy == SeparatorPolicy::Reject && relative_path.contains('\\') {
+        return Err(PathValidationError::BackslashNotAllowed);
+    }
+    let normalized = normalize_separators(relative_path);
+    let s = normalized.trim();
     if s.is_empty() {
         return Err(PathValidationError::EmptyPath);
     }
@@ -181,15 +320,74 @@ pub fn validate_structural_path(relative_path: &str) -> Result<PathBuf, PathVali
     for comp in p.components() {
         if let Component::Normal(os) = comp {
             let s_comp = os.to_string_lossy();
-            if !s_comp.contains('{') {
-                let s_norm = normalize_component(&s_comp);
-                validate_component(&s_norm)?;
+            if template && s_comp.contains('{') {
+                continue;
             }
+            if !template && s_comp.contains(['{', '}']) {
+                return Err(PathValidationError::InvalidComponent(s_comp.into_owned()));
+            }
+            validate_component(&normalize_component(&s_comp))?;
         }
     }
     Ok(p)
 }
 
+/// Validates a **relative path** given as [`Path`], [`PathBuf`] or `&str` like
+/// [`validate_structural_path`].
+///
+/// Paths built programmatically go through the same pipeline without a lossy conversion;
+/// non-UTF-8 input is rejected with [`PathValidationError::NonUtf8Path`].
+///
+/// # Examples
+///
+/// ```rust
+/// use bevy_paths_validation::validate_structural_os_path;
+/// use std::path::Path;
+///
+/// let path = Path::new("saves").join("slot_1");
+/// assert!(validate_structural_os_path(&path).is_ok());
+/// assert!(validate_structural_os_path(Path::new("../escape")).is_err());
+/// ```
+pub fn validate_structural_os_path(
+    relative_path: impl AsRef<Path>,
+) -> Result<PathBuf, PathValidationError> {
+    let path = relative_path.as_ref();
+    let s = path
+        .to_str()
+        .ok_or_else(|| PathValidationError::NonUtf8Path(path.to_path_buf()))?;
+    validate_structural_path(s)
+}
+
+/// How backslashes in relative paths are handled.
+///
+/// Windows accepts both `\` and `/` as separators, while on other platforms a backslash is
+/// part of the file name. Handling them explicitly makes paths behave the same everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeparatorPolicy {
+    /// Treat `\` as a separator and replace it with `/`.
+    #[default]
+    Normalize,
+    /// Reject paths containing `\` with [`PathValidationError::BackslashNotAllowed`].
+    Reject,
+}
+
+/// Replaces every `\` in `path` with `/`.
+///
+/// # Examples
+///
+/// ```rust
+/// use bevy_paths_validation::normalize_separators;
+///
+/// assert_eq!(normalize_separators("saves\\slots"), "saves/slots");
+/// ```
+pub fn normalize_separators(path: &str) -> Cow<'_, str> {
+    if path.contains('\\') {
+        Cow::Owned(path.replace('\\', "/"))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
 /// Normalizes a path component using Unicode NFC normalization.
 ///
 /// This ensures that equivalent Unicode characters (e.g., `é` and `é`) are treated as the same component.
@@ -262,3 +460,197 @@ pub fn validate_component(name: &str) -> Result<(), PathValidationError> {
 
     Ok(())
 }
+
+/// Screens a path component for characters that can spoof another name.
+///
+/// This rejects:
+/// - Zero-width characters (e.g. `U+200B`, `U+FEFF`).
+/// - Bidi control characters (e.g. `U+202E`, which reverses `gpj.exe` into `exe.jpg`).
+/// - Mixed Latin, Greek and Cyrillic letters (e.g. a Cyrillic `а` inside `save`).
+///
+/// This is stricter than [`validate_component`] and meant for user-generated names.
+///
+/// # Examples
+///
+/// ```rust
+/// use bevy_paths_validation::screen_confusables;
+///
+/// assert!(screen_confusables("Сохранение").is_ok());
+/// assert!(screen_confusables("s\u{0430}ve").is_err()); // Cyrillic 'а'
+/// assert!(screen_confusables("photo\u{202E}gpj.exe").is_err());
+/// ```
+pub fn screen_confusables(name: &str) -> Result<(), PathValidationError> {
+    let invisible = |c: char| {
+        matches!(
+            c,
+            '\u{180E}'
+                | '\u{200B}'..='\u{200F}'
+                | '\u{202A}'..='\u{202E}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{2066}'..='\u{2069}'
+                | '\u{061C}'
+                | '\u{FEFF}'
+        )
+    };
+    let script = |c: char| match c {
+        'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' => Some(0),
+        '\u{0370}'..='\u{03FF}' => Some(1),
+        '\u{0400}'..='\u{04FF}' => Some(2),
+        _ => None,
+    };
+    let mut scripts = name.chars().filter_map(script);
+    let mixed = scripts
+        .next()
+        .is_some_and(|first| scripts.any(|other| other != first));
+    if mixed || name.chars().any(invisible) {
+        return Err(PathValidationError::ConfusableComponent(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Upper bounds for relative paths, so runaway template inputs cannot generate
+/// pathological directory trees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PathLimits {
+    /// The maximum number of components.
+    pub max_components: Option<usize>,
+    /// The maximum length of the relative path in bytes.
+    pub max_length: Option<usize>,
+}
+
+/// A single limit of [`PathLimits`], reported by [`PathValidationError::LimitExceeded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathLimit {
+    /// [`PathLimits::max_components`].
+    MaxComponents(usize),
+    /// [`PathLimits::max_length`].
+    MaxLength(usize),
+}
+
+impl std::fmt::Display for PathLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MaxComponents(max) => write!(f, "{max} components"),
+            Self::MaxLength(max) => write!(f, "{max} bytes"),
+        }
+    }
+}
+
+/// Checks a relative path against `limits`.
+///
+/// # Examples
+///
+/// ```rust
+/// use bevy_paths_validation::{validate_limits, PathLimits};
+/// use std::path::Path;
+///
+/// let limits = PathLimits { max_components: Some(2), max_length: None };
+/// assert!(validate_limits(Path::new("saves/slot_1"), &limits).is_ok());
+/// assert!(validate_limits(Path::new("saves/slot_1/world"), &limits).is_err());
+/// ```
+pub fn validate_limits(
+    relative_path: &Path,
+    limits: &PathLimits,
+) -> Result<(), PathValidationError> {
+    let components = relative_path.components().count();
+    if let Some(max) = limits.max_components.filter(|&max| components > max) {
+        return Err(PathValidationError::LimitExceeded {
+            limit: PathLimit::MaxComponents(max),
+            actual: components,
+        });
+    }
+    let length = relative_path.as_os_str().len();
+    if let Some(max) = limits.max_length.filter(|&max| length > max) {
+        return Err(PathValidationError::LimitExceeded {
+            limit: PathLimit::MaxLength(max),
+            actual: length,
+        });
+    }
+    Ok(())
+}
+
+/// A part of a parsed path template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateSegment<'a> {
+    /// Text copied as is.
+    Literal(&'a str),
+    /// A `{name}` placeholder.
+    Placeholder(&'a str),
+    /// A `{@Marker}` reference to the template of another marker.
+    Reference(&'a str),
+}
+
+/// Parses a path template into literal text, placeholders and marker references.
+///
+/// Placeholder names consist of letters, digits and `_`; references may also contain `::`.
+/// Unclosed, unmatched, nested and empty braces are rejected with the byte position of the
+/// error.
+///
+/// # Examples
+///
+/// ```rust
+/// use bevy_paths_validation::{parse_template, PathValidationError, TemplateSegment};
+///
+/// assert_eq!(
+///     parse_template("levels/{id}.map").unwrap(),
+///     [
+///         TemplateSegment::Literal("levels/"),
+///         TemplateSegment::Placeholder("id"),
+///         TemplateSegment::Literal(".map"),
+///     ]
+/// );
+/// assert!(matches!(
+///     parse_template("levels/{id.map"),
+///     Err(PathValidationError::TemplateSyntax { position: 7, .. })
+/// ));
+/// ```
+pub fn parse_template(template: &str) -> Result<Vec<TemplateSegment<'_>>, PathValidationError> {
+    let syntax = |position, reason| PathValidationError::TemplateSyntax { position, reason };
+    let mut segments = Vec::new();
+    let mut literal_start = 0;
+    let mut open = None;
+    for (i, c) in template.char_indices() {
+        match (c, open) {
+            ('{', Some(_)) => return Err(syntax(i, "nested '{'")),
+            ('{', None) => {
+                if literal_start < i {
+                    segments.push(TemplateSegment::Literal(&template[literal_start..i]));
+                }
+                open = Some(i);
+            }
+            ('}', None) => return Err(syntax(i, "'}' without matching '{'")),
+            ('}', Some(start)) => {
+                let field = &template[start + 1..i];
+                let (name, reference) = match field.strip_prefix('@') {
+                    Some(name) => (name, true),
+                    None => (field, false),
+                };
+                if name.is_empty() {
+                    return Err(syntax(start, "empty placeholder"));
+                }
+                let valid = |c: char| c.is_alphanumeric() || c == '_' || (reference && c == ':');
+                if let Some(offset) = name.find(|c: char| !valid(c)) {
+                    return Err(syntax(
+                        i - name.len() + offset,
+                        "invalid character in placeholder name",
+                    ));
+                }
+                segments.push(if reference {
+                    TemplateSegment::Reference(name)
+                } else {
+                    TemplateSegment::Placeholder(name)
+                });
+                open = None;
+                literal_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = open {
+        return Err(syntax(start, "unclosed '{'"));
+    }
+    if literal_start < template.len() {
+        segments.push(TemplateSegment::Literal(&template[literal_start..]));
+    }
+    Ok(segments)
+}